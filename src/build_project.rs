@@ -6,6 +6,9 @@ fn main() {
     build_project("visualizer_1");
     build_project("visualizer_2");
     build_project("trainer");
+    build_project("q_table_inspector");
+    build_project("policy_export");
+    build_project("sweep");
 
     // print message indicating all builds are done
     println!("All done!");
@@ -9,7 +9,7 @@ use charting_tools::{
     charted_coordinate::ChartedCoordinate, charted_map::ChartedMap, charting_bot::ChartingBot,
 };
 use ordered_float::OrderedFloat;
-use rand::{seq::SliceRandom, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use robotics_lib::{
     interface::{destroy, go, put, robot_map, teleport, Direction},
     runner::Runnable,
@@ -25,7 +25,10 @@ use rust_eze_tomtom::{
 use swift_seller::SwiftSeller;
 use who_needs_gv_street_explorer::StreetExplorer;
 
-use super::data_storage;
+use super::data_storage::Recording;
+
+// The contents the robot sells to the markets
+pub(crate) const SELLABLE: [Content; 3] = [Content::Tree(0), Content::Rock(0), Content::Fish(0)];
 
 pub(crate) enum ActionOk {
     Completed,
@@ -145,11 +148,7 @@ pub(crate) fn sell(
 
             let backpack = robot.get_backpack();
             for item in backpack.get_contents().into_iter() {
-                if item.1 > &0
-                    && (item.0 == &Content::Tree(0)
-                        || item.0 == &Content::Rock(0)
-                        || item.0 == &Content::Fish(0))
-                {
+                if item.1 > &0 && SELLABLE.contains(item.0) {
                     to_be_sold.push(item.0.clone());
                 }
             }
@@ -389,6 +388,7 @@ pub(crate) fn explore_nearings(
     robot: &mut impl Runnable,
     world: &mut World,
     look_distance: usize,
+    recording: &RefCell<Recording>,
 ) -> Result<ActionOk, ActionErr> {
     let mut distance = look_distance;
 
@@ -396,7 +396,9 @@ pub(crate) fn explore_nearings(
     if world.get_discoverable() > 0 {
         let res = rust_eze_spotlight::Spotlight::illuminate(robot, world, 10);
 
-        data_storage::update_initial_map(&robot_map(world).unwrap(), true);
+        recording
+            .borrow_mut()
+            .update_initial_map(&robot_map(world).unwrap(), true);
 
         if res.is_ok() {
             return Ok(ActionOk::Completed);
@@ -467,7 +469,9 @@ pub(crate) fn explore_nearings(
         }
     }
 
-    data_storage::update_initial_map(&robot_map(world).unwrap(), true);
+    recording
+        .borrow_mut()
+        .update_initial_map(&robot_map(world).unwrap(), true);
     return Ok(ActionOk::Completed);
 }
 
@@ -658,9 +662,9 @@ fn check_nearings(world: &mut World, pos: (usize, usize)) -> Option<(usize, usiz
 pub(crate) fn explore_unknown(
     robot: &mut impl Runnable,
     world: &mut World,
+    rng: &mut StdRng,
+    recording: &RefCell<Recording>,
 ) -> Result<ActionOk, ActionErr> {
-    let mut rng = rand::thread_rng();
-
     // There is a 10% probability that it explores a street instead of reaching an unknown tile
     if rng.gen::<f64>() > 0.05 {
         let robot_pos = (
@@ -673,19 +677,22 @@ pub(crate) fn explore_unknown(
         let counter = Arc::new(Mutex::new((robot_pos, map, values)));
         let mut handles = vec![];
 
-        // Creates 4 threads, each of them looking for a random unknown tile in the map
+        // Creates 4 threads, each of them looking for a random unknown tile in the map.
+        // Every thread gets its own seed, and the results are collected in spawn order, so the outcome does not depend on scheduling
         for _ in 0..4 {
             let counter = Arc::clone(&counter);
+            let seed = rng.gen::<u64>();
             let handle = thread::spawn(move || {
-                let mut pos_map = counter.lock().unwrap();
-                let val = find_unknown(&pos_map.0, &pos_map.1);
-                pos_map.2.push(val);
+                let mut thread_rng = StdRng::seed_from_u64(seed);
+                let pos_map = counter.lock().unwrap();
+                find_unknown(&pos_map.0, &pos_map.1, &mut thread_rng)
             });
             handles.push(handle);
         }
 
         for handle in handles {
-            handle.join().unwrap();
+            let val = handle.join().unwrap();
+            counter.lock().unwrap().2.push(val);
         }
 
         // It checks all the results returned from the threads and chooses the nearest (considering euclidian distances), valid one
@@ -717,7 +724,7 @@ pub(crate) fn explore_unknown(
         }
 
         // Starts exploring the nearings of the unknown area
-        return explore_nearings(robot, world, 5);
+        return explore_nearings(robot, world, 5, recording);
     }
 
     // It calls the StreetExplorer tool
@@ -731,8 +738,7 @@ pub(crate) fn explore_unknown(
                 return Err(ActionErr::NeedsExploring);
             }
 
-            let mut rng = rand::thread_rng();
-            let destination = destinations.choose(&mut rng);
+            let destination = destinations.choose(rng);
             match destination {
                 Some(coordinates) => {
                     let res = rust_eze_tomtom::TomTom::go_to_coordinates(
@@ -745,7 +751,7 @@ pub(crate) fn explore_unknown(
                         Ok(_) => {}
                         Err(_) => return Err(ActionErr::NeedsExploring),
                     }
-                    return explore_nearings(robot, world, 10);
+                    return explore_nearings(robot, world, 10, recording);
                 }
                 None => return Err(ActionErr::NeedsExploring),
             }
@@ -759,8 +765,8 @@ pub(crate) fn explore_unknown(
 pub(crate) fn find_unknown(
     robot_pos: &(usize, usize),
     map: &Vec<Vec<Option<Tile>>>,
+    rng: &mut impl Rng,
 ) -> Option<(usize, usize)> {
-    let mut rng = rand::thread_rng();
     let size = map.len();
 
     let mut pos = robot_pos.clone();
@@ -772,16 +778,16 @@ pub(crate) fn find_unknown(
         Direction::Left,
         Direction::Right,
     ];
-    let mut dir = directions.choose(&mut rng).unwrap();
+    let mut dir = directions.choose(rng).unwrap();
     let mut max_iterations = size * size;
 
     // The length of one of the sides of the world is used as an upper bound of the number of iterations to avoid infinite loops
     while max_iterations > 0 {
-        dir = directions.choose(&mut rng).unwrap();
+        dir = directions.choose(rng).unwrap();
         while match_coordinates(pos, dir.clone(), size).0 > map.len()
             || match_coordinates(pos, dir.clone(), size).1 > map.len()
         {
-            dir = directions.choose(&mut rng).unwrap();
+            dir = directions.choose(rng).unwrap();
         }
 
         actual_tile = &map[match_coordinates(pos, dir.clone(), size).0]
@@ -1,79 +1,194 @@
-use std::{cell::RefCell, collections::VecDeque, env, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    env,
+    fmt::Display,
+    rc::Rc,
+};
 
 use charting_tools::{charted_map::ChartedMap, ChartingTools};
-use rand::{seq::SliceRandom, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use robotics_lib::{
     runner::{Robot, Runner},
     world::tile::{Content, Tile},
 };
+use serde::{Deserialize, Serialize};
 
+use crate::data_storage::Recording;
 use crate::my_events::MyEvents2;
 
-use super::utils::{load_q_table, write_q_table, Action, MyRobot, States};
-
-// Hyperparameters
-const ALPHA: f64 = 0.1; // LEARNING RATE
-const GAMMA: f64 = 0.9; // DISCOUNT FACTOR
-const EPSILON: f64 = 0.2; // EXPLOITATION VS EXPLORATION
+use super::config::{Mode, TrainingConfig, WorldConfig};
+use super::convergence::QChange;
+use super::goal::{Collectable, Goal, GoalProgress};
+use super::learner::{learner_path, load_learner, Learner, Transition};
+use super::reward::{RewardFunction, TickContext};
+use super::state::{Observation, State, StateKey};
+use super::utils::{Action, ActionMask, MyRobot, QTable, States};
 
 // Default rewards
 // [Start, Goal, Destroyed, Sold, PutInBank, Recycled, NeedsExploring, BackpackFull]
 pub const REWARDS: [f64; 8] = [0.0, 1000.0, -0.5, -0.3, -0.1, -0.1, -1.0, -0.1];
 
+// How an episode ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EpisodeStatus {
+    #[default]
+    GoalReached,
+    // The tick budget ran out
+    TimedOut,
+    // No coins deposited and no new tiles discovered for too long, usually a world without enough content
+    Stalled,
+}
+
+// Summary of a single episode, returned by the training loop and printed as report in evaluation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EpisodeSummary {
+    pub status: EpisodeStatus,
+    pub ticks: usize,
+    pub coins_deposited: usize,
+    pub total_reward: f64,
+    pub energy_spent: usize,
+    pub items_destroyed: usize,
+    pub items_sold: usize,
+    // Exploration rate at the last tick
+    pub epsilon: f64,
+    pub tiles_discovered: usize,
+    // How many times each action has been executed, in the order of Action::ALL
+    pub action_counts: [usize; Action::ALL.len()],
+    // How much the episode changed the Q-table, only measured when the training checks convergence
+    pub q_change: Option<QChange>,
+}
+
+// What happened in a single tick, kept for the per-tick metrics
+#[derive(Debug, Clone, Copy)]
+pub struct TickRecord {
+    pub tick: usize,
+    // The action executed and the state it was chosen in
+    pub state: StateKey,
+    pub action: Action,
+    // What the action led to, before PutInBank and Sold are turned into NeedsExploring for the next choice
+    pub outcome: States,
+    pub reward: f64,
+    pub epsilon: f64,
+    pub energy_consumed: usize,
+    pub coins_deposited: usize,
+    // Tiles discovered since the start of the episode
+    pub tiles_discovered: usize,
+}
+
+impl Display for EpisodeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Status: {:?}", self.status)?;
+        writeln!(f, "Ticks played: {}", self.ticks)?;
+        writeln!(f, "Energy spent: {}", self.energy_spent)?;
+        writeln!(f, "Items destroyed: {}", self.items_destroyed)?;
+        writeln!(f, "Items sold: {}", self.items_sold)?;
+        writeln!(f, "Tiles discovered: {}", self.tiles_discovered)?;
+        writeln!(f, "Coins deposited: {}", self.coins_deposited)?;
+        write!(f, "Total reward: {:.2}", self.total_reward)
+    }
+}
+
+// Everything a simulation produced, owned by the caller once ai() returns
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub world_size: usize,
+    pub goal: Goal,
+    // Spawn position, initial status of the explored tiles and events, replayed by visualizer_1
+    pub recording: Recording,
+    // Events and robot_map after every tick, replayed by visualizer_2
+    pub robot_events: VecDeque<MyEvents2>,
+    pub maps: Vec<Vec<Vec<Option<Tile>>>>,
+    // The Q-table at the end of the simulation
+    pub q_table: QTable,
+    // Terminal status and statistics of the episode
+    pub summary: EpisodeSummary,
+    pub tick_log: Vec<TickRecord>,
+}
+
 pub fn ai(
     world_size: usize,
-    rewards: Vec<f64>,
+    world: &WorldConfig,
+    rewards: &dyn RewardFunction,
     default_rewards: bool,
-) -> (
-    Rc<RefCell<VecDeque<MyEvents2>>>,
-    Rc<RefCell<Vec<Vec<Vec<Option<Tile>>>>>>,
-) {
+    config: &TrainingConfig,
+    seed: u64,
+    mode: Mode,
+) -> SimulationResult {
     env::set_var("RUST_BACKTRACE", "1");
 
-    let mut rng = rand::thread_rng();
+    // Every random decision of the simulation comes from this generator, so the same seed gives the same run
+    let mut rng = StdRng::seed_from_u64(seed);
 
-    if rewards.len() != 8 {
-        panic!("The number of rewards inserted is not the correct one");
-    }
+    // Initialize the Q-table, or the weights of the linear approximation
+    let mut learner = match load_learner(config, default_rewards, rng.gen::<u64>()) {
+        Ok(learner) => learner,
+        Err(e) => panic!("{e}"),
+    };
 
-    // Goal which ends training
-    let goal = States::Goal;
-    let mut coins_to_deposit = ((world_size * world_size) as f32 * 0.002) as usize; //Needed coins to reach goal
-    let total_coins = coins_to_deposit;
-    println!(
-        "Deposited: {}%",
-        ((1.0 - coins_to_deposit as f32 / total_coins as f32) * 100.0) as usize
+    let result = run_episode(
+        world_size,
+        world,
+        rewards,
+        learner.as_mut(),
+        config,
+        0,
+        rng.gen::<u64>(),
+        &mut rng,
+        mode,
     );
 
-    // Initialize Q-table
-    let q_table_res = load_q_table(default_rewards);
-    let mut q_table = match q_table_res {
-        Ok(table) => table,
-        Err(e) => panic!("Q-Table loading: {e}"),
-    };
+    match mode {
+        // Write results
+        Mode::Training => {
+            if let Err(e) = learner.save(&learner_path(config, default_rewards)) {
+                panic!("Q-Table writing: {e}");
+            }
+        }
+        Mode::Evaluation => println!("{}", result.summary),
+    }
+
+    return result;
+}
 
-    // Actions
-    let actions = vec![
-        Action::Recycle,
-        Action::Sell,
-        Action::DestroyTree,
-        Action::DestroyRock,
-        Action::DestroyGarbage,
-        Action::DestroyCoin,
-        Action::DestroyFish,
-        Action::DepositInBank,
-        Action::ExploreNearings,
-        Action::ExploreUnknown,
-    ];
+// Runs a single episode on a freshly generated world, built with the given settings and seed, updating the given learner.
+// first_tick is the number of ticks already played in previous episodes, so that the exploration schedule keeps going.
+// The robot's choices and the exploration draw from rng, so the same world can be played with different policies.
+// In evaluation mode the robot always takes the greedy action and the learner is not updated
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_episode(
+    world_size: usize,
+    world: &WorldConfig,
+    rewards: &dyn RewardFunction,
+    learner: &mut dyn Learner,
+    config: &TrainingConfig,
+    first_tick: usize,
+    world_seed: u64,
+    rng: &mut StdRng,
+    mode: Mode,
+) -> SimulationResult {
+    // Goal which ends training
+    let goal = States::Goal;
+    let mission = config
+        .goal
+        .clone()
+        .unwrap_or_else(|| Goal::for_world(world_size));
+    let mut progress = GoalProgress::default();
+    let mut completion = 0;
+    println!("{mission}: {completion}%");
+    let minimum_coins = mission.coins_needed() * 10; // The world must have enough coins for the goal
 
     // Initialize the world generator
     let mut generator = who_needs_gv_world_generator::WorldGenerator::new(world_size);
-    generator.set_biome_size(0.25);
-    generator.set_cities(true);
-    generator.set_teleports_and_bridges(true);
-    generator.set_minimum_coin_to_deposit(coins_to_deposit * 10);
-    generator.set_minimum_interaction_with_markets(coins_to_deposit * 10);
-    generator.set_rivers(false);
+    generator.set_biome_size(world.biome_size as _);
+    generator.set_cities(world.cities);
+    generator.set_teleports_and_bridges(world.teleports_and_bridges);
+    generator.set_minimum_coin_to_deposit(world.minimum_coins.unwrap_or(minimum_coins));
+    generator.set_minimum_interaction_with_markets(
+        world.minimum_market_interactions.unwrap_or(minimum_coins),
+    );
+    generator.set_rivers(world.rivers);
+    generator.set_seed(world_seed);
 
     /***** DEBUG */
 
@@ -94,6 +209,9 @@ pub fn ai(
     }
 
     let internal_map = Rc::new(RefCell::new(cm.unwrap()));
+    let energy_consumed = Rc::new(RefCell::new(0));
+    let recording = Rc::new(RefCell::new(Recording::default()));
+    let observation = Rc::new(RefCell::new(Observation::default()));
 
     // Initialize the robot
     let my_robot = MyRobot {
@@ -103,90 +221,209 @@ pub fn ai(
         charted_map: Rc::clone(&internal_map),
         past_events: Rc::clone(&pointer_to_events),
         map: Rc::clone(&pointer_to_content_location),
+        rng: Rc::new(RefCell::new(StdRng::seed_from_u64(rng.gen::<u64>()))),
+        energy_consumed: Rc::clone(&energy_consumed),
+        observation: Rc::clone(&observation),
+        recording: Rc::clone(&recording),
+        rest_threshold: config.rest_threshold,
     };
 
     let mut runner = Runner::new(Box::new(my_robot), &mut generator);
+    let mut tick = 0;
+    let mut summary = EpisodeSummary::default();
+    let mut tick_log = Vec::new();
+    // Copying the whole table every episode is only worth it to tell when the training has converged
+    let initial_table = match mode {
+        Mode::Training if config.convergence.is_some() => Some(learner.q_table()),
+        _ => None,
+    };
+
+    // Exploration rate of the policy at the given tick
+    let epsilon = |tick: usize| match mode {
+        Mode::Training => config.exploration.epsilon(first_tick + tick),
+        Mode::Evaluation => 0.0,
+    };
+
+    // The state is the outcome of the last action together with what the robot observes
+    let mut current = State::new(States::Start, &observation.borrow());
+
+    // Select action: exploration vs exploitation, only among the actions that can succeed
+    let mask = ActionMask::new(&observation.borrow());
+    let mut action = learner.select_action(current, mask, epsilon(tick), rng);
+
+    // Last tick in which the robot deposited coins or discovered new tiles
+    let mut last_progress = 0;
+    let mut discovered_tiles = 0;
 
     // Until the goal isn't reached, we stay in the loop that calls the game_tick()
     while initial_state.borrow().clone() != goal {
-        // Select action: exploration vs exploitation
-        let action = if rng.gen::<f64>() < EPSILON {
-            actions.choose(&mut rng).unwrap()
-        } else {
-            actions
-                .iter()
-                .max_by(|&&a1, &&a2| {
-                    q_table[&(initial_state.as_ref().borrow().clone(), a1)]
-                        .partial_cmp(&q_table[&(initial_state.as_ref().borrow().clone(), a2)])
-                        .unwrap()
-                })
-                .unwrap()
-        };
-
         // Execute the chosen action
         internal_action.replace(action.clone());
+        let energy_before = energy_consumed.borrow().clone();
+        let items_before = observation.borrow().backpack_used;
+        let contents_before = observation.borrow().backpack_contents.clone();
         let _ = runner.as_mut().unwrap().game_tick();
+        tick += 1;
+
+        let state = internal_state.borrow().clone();
 
         // Reward function
-        let reward = match &initial_state.as_ref().borrow().clone() {
-            States::Start => rewards[0],
-            States::Goal => rewards[1],
-            States::Destroyed => rewards[2],
-            States::Sold(_) => rewards[3],
-            States::PutInBank(_) => rewards[4],
-            States::Recycled => rewards[5],
-            States::NeedsExploring => rewards[6],
-            States::BackpackFullCoins => rewards[7],
-            States::BackpackFullItems => rewards[7],
-            States::Neutral => -1.0,
+        let context = TickContext {
+            previous: initial_state.borrow().clone(),
+            action,
+            state,
+            energy_consumed: energy_consumed.borrow().clone() - energy_before,
+            coins_deposited: match state {
+                States::PutInBank(n) => n,
+                _ => 0,
+            },
+            items_gained: observation
+                .borrow()
+                .backpack_used
+                .saturating_sub(items_before),
         };
-
-        let state = internal_state.borrow().clone();
+        let reward = rewards.reward(&context);
+        summary.total_reward += reward;
+        summary.action_counts[action.index()] += 1;
+        tick_log.push(TickRecord {
+            tick,
+            state: current.key,
+            action,
+            outcome: state,
+            reward,
+            epsilon: epsilon(tick - 1),
+            energy_consumed: context.energy_consumed,
+            coins_deposited: context.coins_deposited,
+            tiles_discovered: observation.borrow().discovered_tiles,
+        });
 
         // If the last state was PutInBank(n), we update the counter of the coind left to deposit
         match state {
+            // A single destroy can put several items in the backpack
+            States::Destroyed => {
+                summary.items_destroyed += context.items_gained;
+            }
             States::PutInBank(n) => {
-                let mut to_subtract = n;
-
-                while coins_to_deposit > 0 && to_subtract > 0 {
-                    coins_to_deposit -= 1;
-                    to_subtract -= 1;
-                }
-
-                println!(
-                    "Deposited: {}%",
-                    ((1.0 - coins_to_deposit as f32 / total_coins as f32) * 100.0) as usize
-                );
+                summary.coins_deposited += n;
                 let _ = internal_state.replace(States::NeedsExploring);
             }
-            States::Sold(_) => {
+            States::Sold(n) => {
+                summary.items_sold += n;
                 let _ = internal_state.replace(States::NeedsExploring);
             }
             _ => {}
         }
 
-        // Q-learning update
-        let next_max = actions
-            .iter()
-            .map(|a| q_table[&(initial_state.as_ref().borrow().clone(), a.clone())])
-            .fold(f64::MIN, f64::max);
-
-        let q_value = q_table
-            .get_mut(&(initial_state.as_ref().borrow().clone(), action.clone()))
-            .unwrap();
+        // Progress toward the goal, checked at every tick
+        update_progress(
+            &mut progress,
+            &context,
+            &contents_before,
+            &observation.borrow(),
+            world_size,
+        );
+        let new_completion = (mission.completion(&progress) * 100.0) as usize;
+        let goal_progressed = new_completion > completion;
+        if goal_progressed {
+            completion = new_completion;
+            println!("{mission}: {completion}%");
+        }
 
-        // q-learning function
-        *q_value = *q_value * (1.0 - ALPHA) + ALPHA * (reward + GAMMA * next_max - *q_value);
+        // The next action is chosen before the update, SARSA needs to know it
+        let next_state = internal_state.borrow().clone();
+        let next = State::new(next_state, &observation.borrow());
+        let next_mask = ActionMask::new(&observation.borrow());
+        let next_action = learner.select_action(next, next_mask, epsilon(tick), rng);
+
+        if mode == Mode::Training {
+            let transition = Transition {
+                state: current,
+                action,
+                reward,
+                next_state: next,
+                next_mask,
+                next_action,
+            };
+            learner.update(&transition, epsilon(tick - 1));
+        }
 
-        initial_state.replace(internal_state.borrow().clone());
+        initial_state.replace(next_state);
+        current = next;
+        action = next_action;
 
-        if coins_to_deposit <= 0 {
+        if mission.is_reached(&progress) {
             initial_state.replace(States::Goal);
+            continue;
+        }
+
+        // Episodes that cannot reach the goal end here, and the caller saves what has been learned so far
+        if goal_progressed
+            || context.coins_deposited > 0
+            || observation.borrow().discovered_tiles > discovered_tiles
+        {
+            discovered_tiles = observation.borrow().discovered_tiles;
+            last_progress = tick;
+        }
+        if let Some(stall_ticks) = config.limits.stall_ticks {
+            if tick - last_progress >= stall_ticks {
+                summary.status = EpisodeStatus::Stalled;
+                break;
+            }
+        }
+        if let Some(max_ticks) = config.limits.max_ticks {
+            if tick >= max_ticks {
+                summary.status = EpisodeStatus::TimedOut;
+                break;
+            }
         }
     }
 
-    // Write results
-    write_q_table(q_table, default_rewards);
+    summary.ticks = tick;
+    summary.energy_spent = energy_consumed.borrow().clone();
+    summary.epsilon = epsilon(tick.saturating_sub(1));
+    summary.tiles_discovered = observation.borrow().discovered_tiles;
+    let q_table = learner.q_table();
+    summary.q_change =
+        initial_table.map(|initial_table| QChange::between(&initial_table, &q_table));
+
+    return SimulationResult {
+        world_size,
+        goal: mission,
+        recording: recording.take(),
+        robot_events: pointer_to_events.take(),
+        maps: pointer_to_content_location.take(),
+        q_table,
+        summary,
+        tick_log,
+    };
+}
+
+// Adds what happened in a tick to the progress toward the goal
+fn update_progress(
+    progress: &mut GoalProgress,
+    context: &TickContext,
+    contents_before: &HashMap<Content, usize>,
+    observation: &Observation,
+    world_size: usize,
+) {
+    progress.coins_deposited += context.coins_deposited;
+
+    let count = |contents: &HashMap<Content, usize>, item: Collectable| {
+        *contents.get(&item.content()).unwrap_or(&0)
+    };
+    for item in Collectable::ALL {
+        let before = count(contents_before, item);
+        let after = count(&observation.backpack_contents, item);
+        if after > before {
+            *progress.collected.entry(item).or_insert(0) += after - before;
+        }
+    }
+
+    if context.state == States::Recycled {
+        let before = count(contents_before, Collectable::Garbage);
+        let after = count(&observation.backpack_contents, Collectable::Garbage);
+        progress.garbage_recycled += before.saturating_sub(after);
+    }
 
-    return (pointer_to_events, pointer_to_content_location);
+    progress.explored = observation.discovered_tiles as f64 / (world_size * world_size) as f64;
 }
@@ -0,0 +1,114 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::ai::EpisodeSummary;
+use super::config::{TrainingConfig, WorldConfig};
+use super::reward::TableRewards;
+use super::training::TrainerConfig;
+
+// Everything a training session needs to continue where it stopped. The learner is written to its own file,
+// which the checkpoint points to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    // Settings of the session, a session with other ones would not play the same episodes
+    pub seed: u64,
+    pub world_size: usize,
+    pub episodes: usize,
+    pub generator: WorldConfig,
+    pub training: TrainingConfig,
+    pub rewards: TableRewards,
+    // File of the learner, two files are used in turn so that the one of the last complete checkpoint
+    // is never overwritten
+    pub learner: String,
+    // The session RNG is reseeded with this seed after every episode (every round when parallel),
+    // so that its whole state fits in a number
+    pub rng_seed: u64,
+    // Ticks played so far, the position of the exploration schedule
+    pub ticks_played: usize,
+    // Stable episodes in a row counted by the convergence check
+    pub stable_episodes: usize,
+    // One summary per episode played, their number is the episode counter
+    pub summaries: Vec<EpisodeSummary>,
+}
+
+impl Checkpoint {
+    // The state of a session that has not played any episode yet
+    pub(crate) fn new(config: &TrainerConfig, episodes: usize, rng_seed: u64) -> Self {
+        Checkpoint {
+            seed: config.seed,
+            world_size: config.world_size,
+            episodes,
+            generator: config.generator.clone(),
+            training: config.training.clone(),
+            rewards: config.rewards,
+            rng_seed,
+            ..Checkpoint::default()
+        }
+    }
+
+    // Checks that the checkpoint was written by a session with the same settings as the given one
+    pub(crate) fn check(&self, config: &TrainerConfig, episodes: usize) -> Result<(), String> {
+        if self.seed != config.seed {
+            return Err(format!(
+                "The checkpoint was written with seed {}, not {}",
+                self.seed, config.seed
+            ));
+        }
+        if self.world_size != config.world_size {
+            return Err(format!(
+                "The checkpoint was written with world size {}, not {}",
+                self.world_size, config.world_size
+            ));
+        }
+        if self.episodes != episodes {
+            return Err(format!(
+                "The checkpoint was written for {} episodes, not {episodes}",
+                self.episodes
+            ));
+        }
+        if self.generator != config.generator {
+            return Err("The checkpoint was written with other world settings".to_string());
+        }
+        if self.training != config.training {
+            return Err("The checkpoint was written with other hyperparameters".to_string());
+        }
+        if self.rewards != config.rewards {
+            return Err("The checkpoint was written with other rewards".to_string());
+        }
+        Ok(())
+    }
+
+    // Loads a checkpoint written by write()
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(|e| format!("File read: {e}"))?;
+
+        ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))
+    }
+
+    // Where the learner of the next checkpoint, written at path, goes: the file of this checkpoint stays valid
+    // until the new checkpoint replaces it
+    pub fn next_learner_path(&self, path: &str) -> String {
+        let first = format!("{path}.learner0");
+        if self.learner == first {
+            format!("{path}.learner1")
+        } else {
+            first
+        }
+    }
+
+    // Writes the checkpoint to a temporary file first, so that a session killed while writing
+    // keeps the previous checkpoint
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let serialized = ron::to_string(self).map_err(|e| format!("Serializing: {e}"))?;
+        let temporary = format!("{path}.tmp");
+        fs::write(&temporary, serialized).map_err(|e| format!("File write: {e}"))?;
+        fs::rename(&temporary, path).map_err(|e| format!("File rename: {e}"))
+    }
+}
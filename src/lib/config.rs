@@ -0,0 +1,302 @@
+use std::{fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use super::convergence::ConvergenceConfig;
+use super::goal::Goal;
+use super::learner::Algorithm;
+use super::replay::{ReplayConfig, Sampling};
+
+// Default hyperparameters, the ones used to train the default robot
+pub const ALPHA: f64 = 0.1; // LEARNING RATE
+pub const GAMMA: f64 = 0.9; // DISCOUNT FACTOR
+pub const EPSILON: f64 = 0.2; // EXPLOITATION VS EXPLORATION
+pub const REST_THRESHOLD: usize = 700; // ENERGY AT WHICH THE DEFAULT ROBOT STOPS TO RECHARGE
+
+// Reads the seed of the simulation from the first command line argument, or picks a random one
+pub fn seed_from_args() -> u64 {
+    let seed = std::env::args().nth(1).map(|arg| {
+        arg.parse::<u64>()
+            .expect("The seed must be a positive number.")
+    });
+    seed_or_random(seed)
+}
+
+// The given seed, or a random one if None. The seed is printed so that the run can be reproduced
+pub fn seed_or_random(seed: Option<u64>) -> u64 {
+    let seed = seed.unwrap_or_else(rand::random::<u64>);
+    println!("Seed: {seed}");
+    seed
+}
+
+// Training explores and updates the Q-table, Evaluation only follows the greedy policy and leaves the table untouched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Training,
+    Evaluation,
+}
+
+// How the exploration rate evolves while the robot is learning, measured in game ticks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Exploration {
+    // Always the same epsilon
+    Constant(f64),
+    // Goes from start to end in the given number of ticks, then stays at end
+    LinearDecay { start: f64, end: f64, ticks: usize },
+    // Multiplied by decay at every tick, without going below floor
+    ExponentialDecay { start: f64, decay: f64, floor: f64 },
+}
+
+impl Exploration {
+    // Checks that every rate is between 0 and 1 and that a decay does not make epsilon grow
+    pub fn validate(&self) -> Result<(), String> {
+        let rates = match self {
+            Exploration::Constant(epsilon) => vec![*epsilon],
+            Exploration::LinearDecay { start, end, .. } => vec![*start, *end],
+            Exploration::ExponentialDecay {
+                start,
+                decay,
+                floor,
+            } => {
+                if !(*decay > 0.0 && *decay <= 1.0) {
+                    return Err(format!("Invalid exploration decay: {decay}"));
+                }
+                vec![*start, *floor]
+            }
+        };
+        match rates.iter().find(|rate| !(0.0..=1.0).contains(*rate)) {
+            Some(rate) => Err(format!("Invalid exploration rate: {rate}")),
+            None => Ok(()),
+        }
+    }
+
+    // Returns the exploration rate to use at the given tick
+    pub fn epsilon(&self, tick: usize) -> f64 {
+        match self {
+            Exploration::Constant(epsilon) => *epsilon,
+            Exploration::LinearDecay { start, end, ticks } => {
+                if tick >= *ticks {
+                    return *end;
+                }
+                start + (end - start) * (tick as f64 / *ticks as f64)
+            }
+            Exploration::ExponentialDecay {
+                start,
+                decay,
+                floor,
+            } => f64::max(start * decay.powf(tick as f64), *floor),
+        }
+    }
+}
+
+// Settings of the world generator, the defaults are the worlds the default robot was trained on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub biome_size: f64,
+    pub cities: bool,
+    pub teleports_and_bridges: bool,
+    pub rivers: bool,
+    // Coins the world must let the robot deposit and interactions its markets must allow,
+    // None derives them from the coins the goal of the episode needs
+    pub minimum_coins: Option<usize>,
+    pub minimum_market_interactions: Option<usize>,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        WorldConfig {
+            biome_size: 0.25,
+            cities: true,
+            teleports_and_bridges: true,
+            rivers: false,
+            minimum_coins: None,
+            minimum_market_interactions: None,
+        }
+    }
+}
+
+impl WorldConfig {
+    // Loads the world settings from a RON file, missing fields take the default value
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(|e| format!("File read: {e}"))?;
+
+        let config: WorldConfig =
+            ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // Checks the settings the world generator would not accept
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.biome_size) {
+            return Err(format!("Invalid biome size: {}", self.biome_size));
+        }
+        Ok(())
+    }
+
+    // Named variants of the default worlds, to train and evaluate the robot on different maps
+    pub fn presets() -> Vec<(&'static str, WorldConfig)> {
+        let default = WorldConfig::default();
+        vec![
+            ("Default", default.clone()),
+            (
+                "Rivers",
+                WorldConfig {
+                    rivers: true,
+                    ..default.clone()
+                },
+            ),
+            (
+                "No teleports",
+                WorldConfig {
+                    teleports_and_bridges: false,
+                    ..default.clone()
+                },
+            ),
+            (
+                "Rivers, no teleports",
+                WorldConfig {
+                    rivers: true,
+                    teleports_and_bridges: false,
+                    ..default
+                },
+            ),
+        ]
+    }
+}
+
+// When an episode that cannot reach the goal is stopped: after max_ticks ticks, or after stall_ticks ticks
+// without depositing coins nor discovering new tiles. None disables the check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpisodeLimits {
+    pub max_ticks: Option<usize>,
+    pub stall_ticks: Option<usize>,
+}
+
+impl Default for EpisodeLimits {
+    fn default() -> Self {
+        EpisodeLimits {
+            max_ticks: Some(20_000),
+            stall_ticks: Some(2_000),
+        }
+    }
+}
+
+// How the robot stores what it learns: one value per state and action, or a linear function of the state features
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ValueFunction {
+    #[default]
+    Table,
+    Linear,
+}
+
+// Hyperparameters of the learning algorithm, missing fields take the default value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrainingConfig {
+    pub algorithm: Algorithm,
+    pub value_function: ValueFunction,
+    pub learning_rate: f64,
+    pub discount: f64,
+    pub exploration: Exploration,
+    pub limits: EpisodeLimits,
+    // What the robot has to achieve, None for the default goal of the world size
+    pub goal: Option<Goal>,
+    // At or below this energy the robot rests whatever the policy chose. None leaves it to the Rest action
+    pub rest_threshold: Option<usize>,
+    // Transitions kept and learned from again, None learns from each of them only once
+    pub replay: Option<ReplayConfig>,
+    // Stops the training once the Q-table stops changing, None plays every episode
+    pub convergence: Option<ConvergenceConfig>,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        TrainingConfig {
+            algorithm: Algorithm::QLearning,
+            value_function: ValueFunction::Table,
+            learning_rate: ALPHA,
+            discount: GAMMA,
+            exploration: Exploration::Constant(EPSILON),
+            limits: EpisodeLimits::default(),
+            goal: None,
+            rest_threshold: Some(REST_THRESHOLD),
+            replay: None,
+            convergence: None,
+        }
+    }
+}
+
+impl TrainingConfig {
+    // Loads the hyperparameters from a RON file, the same way the trainer reads rewards.ron
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(|e| format!("File read: {e}"))?;
+
+        let config: TrainingConfig =
+            ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // Checks the values that cannot be expressed by the types, like rates outside [0, 1]
+    pub fn validate(&self) -> Result<(), String> {
+        let config = self;
+        if !(0.0..=1.0).contains(&config.learning_rate) {
+            return Err(format!("Invalid learning rate: {}", config.learning_rate));
+        }
+        if !(0.0..=1.0).contains(&config.discount) {
+            return Err(format!("Invalid discount factor: {}", config.discount));
+        }
+        config.exploration.validate()?;
+        // An episode in a world where the goal cannot be reached would never end
+        if config.limits.max_ticks.is_none() && config.limits.stall_ticks.is_none() {
+            return Err("At least one of max_ticks and stall_ticks must be set".to_string());
+        }
+        if config.value_function == ValueFunction::Linear
+            && config.algorithm == Algorithm::DoubleQLearning
+        {
+            return Err("DoubleQLearning is only available with the Q-table".to_string());
+        }
+        if let Some(replay) = &config.replay {
+            // Replayed transitions were chosen by an older policy, SARSA would learn the wrong one
+            if config.algorithm == Algorithm::Sarsa {
+                return Err("Experience replay is not available with Sarsa".to_string());
+            }
+            if let Sampling::Prioritized {
+                exponent,
+                correction,
+            } = replay.sampling
+            {
+                if exponent < 0.0 {
+                    return Err(format!("Invalid prioritization exponent: {exponent}"));
+                }
+                if !(0.0..=1.0).contains(&correction) {
+                    return Err(format!(
+                        "Invalid importance sampling correction: {correction}"
+                    ));
+                }
+            }
+        }
+
+        if let Some(convergence) = &config.convergence {
+            if convergence.max_delta < 0.0 || convergence.mean_delta < 0.0 {
+                return Err("The convergence thresholds cannot be negative".to_string());
+            }
+            if convergence.patience == 0 {
+                return Err("The convergence patience must be at least 1 episode".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use super::state::StateKey;
+use super::utils::{Action, QTable};
+
+// How much an episode changed the Q-table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct QChange {
+    // Largest and mean absolute change of a value
+    pub max_delta: f64,
+    pub mean_delta: f64,
+    // States whose greedy action is not the same anymore
+    pub policy_changes: usize,
+}
+
+impl QChange {
+    // Compares the table at the start of an episode with the one at the end
+    pub fn between(before: &QTable, after: &QTable) -> Self {
+        let mut max_delta: f64 = 0.0;
+        let mut total = 0.0;
+        for (key, value) in after {
+            let delta = (value - before.get(key).copied().unwrap_or(0.0)).abs();
+            max_delta = max_delta.max(delta);
+            total += delta;
+        }
+
+        QChange {
+            max_delta,
+            mean_delta: total / after.len().max(1) as f64,
+            policy_changes: StateKey::all()
+                .into_iter()
+                .filter(|state| greedy(before, *state) != greedy(after, *state))
+                .count(),
+        }
+    }
+}
+
+// Best action of the state, not masked: the table does not know what the robot could do
+fn greedy(q_table: &QTable, state: StateKey) -> Action {
+    let value = |a: &Action| q_table.get(&(state, *a)).copied().unwrap_or(0.0);
+    Action::ALL
+        .into_iter()
+        .max_by(|a1, a2| value(a1).total_cmp(&value(a2)))
+        .unwrap()
+}
+
+// The training stops once patience episodes in a row changed the table less than every threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvergenceConfig {
+    pub max_delta: f64,
+    pub mean_delta: f64,
+    pub policy_changes: usize,
+    pub patience: usize,
+}
+
+impl Default for ConvergenceConfig {
+    fn default() -> Self {
+        ConvergenceConfig {
+            max_delta: 1e-2,
+            mean_delta: 1e-4,
+            policy_changes: 0,
+            patience: 5,
+        }
+    }
+}
+
+impl ConvergenceConfig {
+    fn is_stable(&self, change: &QChange) -> bool {
+        change.max_delta <= self.max_delta
+            && change.mean_delta <= self.mean_delta
+            && change.policy_changes <= self.policy_changes
+    }
+}
+
+// Counts the stable episodes in a row, without a config the training never converges
+pub struct ConvergenceTracker {
+    config: Option<ConvergenceConfig>,
+    stable: usize,
+}
+
+impl ConvergenceTracker {
+    pub fn new(config: Option<ConvergenceConfig>) -> Self {
+        ConvergenceTracker { config, stable: 0 }
+    }
+
+    // Continues the count of a checkpoint
+    pub fn resume(config: Option<ConvergenceConfig>, stable: usize) -> Self {
+        ConvergenceTracker { config, stable }
+    }
+
+    // Stable episodes in a row so far
+    pub fn stable_episodes(&self) -> usize {
+        self.stable
+    }
+
+    // Takes the change of the next episode, returns whether the training has converged.
+    // Episodes are only measured when there is a config
+    pub fn observe(&mut self, change: Option<QChange>) -> bool {
+        let (Some(config), Some(change)) = (&self.config, change) else {
+            return false;
+        };
+        if config.is_stable(&change) {
+            self.stable += 1;
+        } else {
+            self.stable = 0;
+        }
+        self.stable >= config.patience
+    }
+}
@@ -0,0 +1,170 @@
+use std::{fs::File, io::Read};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::ai::{run_episode, EpisodeStatus};
+use super::config::{Mode, WorldConfig};
+use super::convergence::ConvergenceTracker;
+use super::training::{create_metrics, report_episode, StopReason, TrainerConfig, TrainingReport};
+
+// A stage of the curriculum, played on worlds of the same size until enough episodes reach the goal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurriculumStage {
+    pub world_size: usize,
+    pub episodes: usize,
+    pub generator: WorldConfig,
+    // Fraction of the stage's episodes that must reach the goal to move to the next stage
+    pub success_rate: f64,
+    // How many times the stage is played before giving up on the curriculum
+    pub max_attempts: usize,
+}
+
+impl Default for CurriculumStage {
+    fn default() -> Self {
+        CurriculumStage {
+            world_size: 30,
+            episodes: 10,
+            generator: WorldConfig::default(),
+            success_rate: 0.8,
+            max_attempts: 5,
+        }
+    }
+}
+
+// Loads the stages from a RON file, a list of CurriculumStage
+pub fn load_curriculum(path: &str) -> Result<Vec<CurriculumStage>, String> {
+    let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+    let mut buffer = String::new();
+    file.read_to_string(&mut buffer)
+        .map_err(|e| format!("File read: {e}"))?;
+
+    ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))
+}
+
+// Plays the stages of config.curriculum in order, carrying the Q-table between them.
+// A stage is played again until its success rate is met, after max_attempts the curriculum stops there.
+// What has been learned is written after every attempt. Convergence, tracked from the start of each stage,
+// ends the attempt early: the stage is not played again, since the Q-table would not change anymore
+pub fn train_curriculum(config: &TrainerConfig) -> Result<TrainingReport, String> {
+    let stages = &config.curriculum;
+    if stages.is_empty() {
+        return Err("The curriculum has no stages".to_string());
+    }
+    config.validate()?;
+    if let Some(i) = stages
+        .iter()
+        .position(|s| s.episodes == 0 || s.max_attempts == 0)
+    {
+        return Err(format!(
+            "Stage {} must have at least one episode and one attempt",
+            i + 1
+        ));
+    }
+
+    // A curriculum keeps no checkpoint, what has been learned is in the learner file
+    if config.resume {
+        return Err("Resuming is not available with a curriculum".to_string());
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut learner = config.initial_learner(rng.gen::<u64>())?;
+    let path = config.output_path();
+    let mut summaries = Vec::new();
+    let mut ticks_played = 0;
+    let mut metrics = create_metrics(config, &[])?;
+    let mut stop = StopReason::Completed;
+
+    for (i, stage) in stages.iter().enumerate() {
+        let mut passed = false;
+        let mut convergence = ConvergenceTracker::new(config.training.convergence.clone());
+        let mut converged = false;
+
+        for attempt in 1..=stage.max_attempts {
+            println!(
+                "Stage {}/{}, attempt {attempt}: {} episodes on {}x{} worlds",
+                i + 1,
+                stages.len(),
+                stage.episodes,
+                stage.world_size,
+                stage.world_size
+            );
+
+            let mut reached = 0;
+            let mut played = 0;
+            for _ in 0..stage.episodes {
+                let result = run_episode(
+                    stage.world_size,
+                    &stage.generator,
+                    &config.rewards,
+                    learner.as_mut(),
+                    &config.training,
+                    ticks_played,
+                    rng.gen::<u64>(),
+                    &mut rng,
+                    Mode::Training,
+                );
+                ticks_played += result.summary.ticks;
+                if result.summary.status == EpisodeStatus::GoalReached {
+                    reached += 1;
+                }
+                report_episode(
+                    summaries.len() + 1,
+                    None,
+                    &result.summary,
+                    &result.tick_log,
+                    &mut metrics,
+                )?;
+                played += 1;
+                converged = convergence.observe(result.summary.q_change);
+                summaries.push(result.summary);
+                if converged {
+                    break;
+                }
+            }
+
+            learner
+                .save(&path)
+                .map_err(|e| format!("Q-Table writing: {e}"))?;
+
+            let success_rate = reached as f64 / played as f64;
+            println!(
+                "Success rate {:.0}%, {:.0}% needed",
+                success_rate * 100.0,
+                stage.success_rate * 100.0
+            );
+            if success_rate >= stage.success_rate {
+                passed = true;
+                break;
+            }
+            if converged {
+                break;
+            }
+        }
+
+        if !passed {
+            if converged {
+                println!(
+                    "Curriculum stopped at stage {}/{}: the Q-table converged without meeting the success rate",
+                    i + 1,
+                    stages.len()
+                );
+                stop = StopReason::Converged {
+                    episode: summaries.len(),
+                };
+            } else {
+                println!(
+                    "Curriculum stopped at stage {}/{}: success rate not met in {} attempts",
+                    i + 1,
+                    stages.len(),
+                    stage.max_attempts
+                );
+                stop = StopReason::StageFailed { stage: i + 1 };
+            }
+            break;
+        }
+    }
+
+    Ok(TrainingReport { summaries, stop })
+}
@@ -7,76 +7,90 @@ use robotics_lib::world::tile::Tile;
 use std::collections::{HashMap, VecDeque};
 use std::sync::Mutex;
 
-/// Represents the initial data for the simulation.
+/// Everything the visualizer needs to replay a simulation: where the robot spawned, the tiles it explored in
+/// their initial status and the events fired during the execution of the AI.
 #[derive(Debug, Default, Clone)]
-pub struct InitialData {
+pub struct Recording {
     pub robot_spawn_position: (usize, usize),
+    pub initial_map: HashMap<(usize, usize), Tile>,
+    pub events: VecDeque<MyEvent>,
 }
 
 #[derive(Debug, Clone)]
 pub enum MyEvent {
     RobLib(RoboticLibEvent),
     DiscoveredTiles(HashSet<(usize, usize)>),
-}
-lazy_static! {
-    /// This HashMap holds the tiles the robot will explore, in their initial status.
-    pub static ref INITIAL_MAP: Mutex<HashMap<(usize, usize), Tile>> = Mutex::new(HashMap::new());
+    /// The robot spent the tick recharging instead of acting.
+    Rested,
 }
 
-lazy_static! {
-    /// This VecDeque will hold all the events from the Robotic Lib fired during the execution of the AI.
-    pub static ref SIMULATION_EVENTS: Mutex<VecDeque<MyEvent>> =
-        Mutex::new(VecDeque::new());
-}
-
-lazy_static! {
-    pub static ref SETUP_DATA: Mutex<InitialData> = Mutex::new(InitialData::default());
-}
-
-/// Writes the given event to the SIMULATION_EVENTS for the visualizer.
-pub fn push_event(event: MyEvent) {
-    match event {
-        MyEvent::RobLib(e) => match e {
-            RoboticLibEvent::Ready
-            // | RoboticLibEvent::Terminated
-            | RoboticLibEvent::TimeChanged(_)
-            | RoboticLibEvent::DayChanged(_) => {}
-            e => SIMULATION_EVENTS.lock().unwrap().push_back(MyEvent::RobLib(e)),
-        },
-        e => SIMULATION_EVENTS.lock().unwrap().push_back(e),
+impl Recording {
+    /// Writes the given event to the timeline.
+    pub fn push_event(&mut self, event: MyEvent) {
+        match event {
+            MyEvent::RobLib(e) => match e {
+                RoboticLibEvent::Ready
+                // | RoboticLibEvent::Terminated
+                | RoboticLibEvent::TimeChanged(_)
+                | RoboticLibEvent::DayChanged(_) => {}
+                e => self.events.push_back(MyEvent::RobLib(e)),
+            },
+            e => self.events.push_back(e),
+        }
     }
-}
 
-pub fn update_initial_map(robot_map: &[Vec<Option<Tile>>], discovered: bool) {
-    let mut tiles = HashSet::new();
-    let mut initial_map = INITIAL_MAP.lock().unwrap();
+    /// Saves the tiles seen for the first time, optionally firing a DiscoveredTiles event.
+    pub fn update_initial_map(&mut self, robot_map: &[Vec<Option<Tile>>], discovered: bool) {
+        let mut tiles = HashSet::new();
 
-    for (i, row) in robot_map.iter().enumerate() {
-        for (j, tile) in row.iter().enumerate() {
-            if let Some(t) = tile {
-                if !initial_map.contains_key(&(i, j)) {
-                    tiles.insert((i, j));
+        for (i, row) in robot_map.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if let Some(t) = tile {
+                    if !self.initial_map.contains_key(&(i, j)) {
+                        tiles.insert((i, j));
+                    }
+                    self.initial_map.entry((i, j)).or_insert_with(|| t.clone());
                 }
-                initial_map.entry((i, j)).or_insert_with(|| t.clone());
             }
         }
+
+        if discovered {
+            self.push_event(MyEvent::DiscoveredTiles(tiles));
+        }
     }
 
-    if discovered {
-        push_event(MyEvent::DiscoveredTiles(tiles));
+    /// Save the spawn position of the robot.
+    pub fn save_initial_data(&mut self, robot_spawn_position: (usize, usize)) {
+        self.robot_spawn_position = robot_spawn_position;
     }
 }
 
+lazy_static! {
+    /// Recording of the robots that cannot own one, like the one of the showcase.
+    pub static ref RECORDING: Mutex<Recording> = Mutex::new(Recording::default());
+}
+
+/// Writes the given event to the global RECORDING.
+pub fn push_event(event: MyEvent) {
+    RECORDING.lock().unwrap().push_event(event);
+}
+
+pub fn update_initial_map(robot_map: &[Vec<Option<Tile>>], discovered: bool) {
+    RECORDING
+        .lock()
+        .unwrap()
+        .update_initial_map(robot_map, discovered);
+}
+
 /// Save the spawn posittion and world size plus useful data for the AI mission.
 pub fn save_initial_data(robot_spawn_position: (usize, usize)) {
-    *SETUP_DATA.lock().unwrap() = InitialData {
-        robot_spawn_position,
-    };
+    RECORDING
+        .lock()
+        .unwrap()
+        .save_initial_data(robot_spawn_position);
 }
 
-
-/// Clear the content of the initila map.
-pub fn clear_previous_data() {
-    INITIAL_MAP.lock().unwrap().clear();
-    SIMULATION_EVENTS.lock().unwrap().clear(); 
-}
\ No newline at end of file
+/// Returns the global RECORDING, leaving an empty one for the next simulation.
+pub fn take_recording() -> Recording {
+    std::mem::take(&mut *RECORDING.lock().unwrap())
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::ai::{run_episode, EpisodeStatus, SimulationResult};
+use super::config::Mode;
+use super::learner::new_learner;
+use super::state::StateKey;
+use super::training::TrainerConfig;
+use super::utils::{Action, QTable, States};
+
+// The greedy choice in a state: the best action, its value and how much better it is than the second best
+#[derive(Debug, Clone)]
+pub struct DecisionRow {
+    pub state: String,
+    pub best: Action,
+    pub value: f64,
+    pub margin: f64,
+}
+
+// Best action of a row of values, in the order of Action::ALL
+fn decide(state: String, values: &[f64]) -> DecisionRow {
+    let mut ranked: Vec<(Action, f64)> = Action::ALL.into_iter().zip(values.to_vec()).collect();
+    ranked.sort_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap());
+
+    DecisionRow {
+        state,
+        best: ranked[0].0,
+        value: ranked[0].1,
+        margin: ranked[0].1 - ranked[1].1,
+    }
+}
+
+// One row for each States value, deciding on the mean value over all the observations with that outcome
+pub fn decisions_by_outcome(q_table: &QTable) -> Vec<DecisionRow> {
+    let keys = StateKey::all();
+    States::ALL
+        .iter()
+        .map(|outcome| {
+            let keys: Vec<&StateKey> = keys.iter().filter(|k| k.outcome == *outcome).collect();
+            let mean = |a: &Action| {
+                keys.iter().map(|k| q_table[&(**k, *a)]).sum::<f64>() / keys.len() as f64
+            };
+            let values: Vec<f64> = Action::ALL.iter().map(mean).collect();
+            decide(format!("{outcome:?}"), &values)
+        })
+        .collect()
+}
+
+// One row for every StateKey. The actions are not masked, the table does not know what the robot could do
+pub fn decisions_by_state(q_table: &QTable) -> Vec<DecisionRow> {
+    StateKey::all()
+        .into_iter()
+        .map(|state| {
+            let values: Vec<f64> = Action::ALL.iter().map(|a| q_table[&(state, *a)]).collect();
+            decide(state.to_string(), &values)
+        })
+        .collect()
+}
+
+// The rows as a Markdown table
+pub fn decisions_to_markdown(rows: &[DecisionRow]) -> String {
+    let mut output =
+        String::from("| State | Best action | Q-value | Margin |\n|---|---|---:|---:|\n");
+    for row in rows {
+        output += &format!(
+            "| {} | {:?} | {:.3} | {:.3} |\n",
+            row.state, row.best, row.value, row.margin
+        );
+    }
+    output
+}
+
+// The rows as an HTML table
+pub fn decisions_to_html(rows: &[DecisionRow]) -> String {
+    let mut output = String::from(
+        "<table>\n<tr><th>State</th><th>Best action</th><th>Q-value</th><th>Margin</th></tr>\n",
+    );
+    for row in rows {
+        output += &format!(
+            "<tr><td>{}</td><td>{:?}</td><td>{:.3}</td><td>{:.3}</td></tr>\n",
+            row.state, row.best, row.value, row.margin
+        );
+    }
+    output += "</table>\n";
+    output
+}
+
+// Plays the given number of episodes with the greedy policy of the table, recording what the robot did
+pub fn record_episodes(
+    q_table: QTable,
+    episodes: usize,
+    config: &TrainerConfig,
+) -> Vec<SimulationResult> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut learner = new_learner(&config.training, q_table, rng.gen::<u64>());
+
+    (0..episodes)
+        .map(|_| {
+            run_episode(
+                config.world_size,
+                &config.generator,
+                &config.rewards,
+                learner.as_mut(),
+                &config.training,
+                0,
+                rng.gen::<u64>(),
+                &mut rng,
+                Mode::Evaluation,
+            )
+        })
+        .collect()
+}
+
+// Graphviz graph of the recorded episodes: the nodes are the outcomes, and an edge from one to the other is labelled
+// with the action that led there and how many times it did. The more often, the thicker the edge.
+// An episode that reached the goal adds an edge from the outcome of its last action to Goal
+pub fn transition_graph(results: &[SimulationResult]) -> String {
+    // Ordered, so that the same episodes always give the same file. The action is None for the goal edges
+    let mut edges: BTreeMap<(usize, Option<usize>, usize), usize> = BTreeMap::new();
+    for result in results {
+        for tick in &result.tick_log {
+            let key = (
+                outcome_index(tick.state.outcome),
+                Some(tick.action.index()),
+                outcome_index(tick.outcome),
+            );
+            *edges.entry(key).or_insert(0) += 1;
+        }
+        if let (EpisodeStatus::GoalReached, Some(last)) =
+            (result.summary.status, result.tick_log.last())
+        {
+            let key = (
+                outcome_index(last.outcome),
+                None,
+                outcome_index(States::Goal),
+            );
+            *edges.entry(key).or_insert(0) += 1;
+        }
+    }
+    let most = edges.values().copied().max().unwrap_or(1) as f64;
+
+    let mut output = String::from("digraph policy {\n    rankdir=LR;\n");
+    for outcome in States::ALL {
+        output += &format!("    \"{outcome:?}\";\n");
+    }
+    for ((from, action, to), count) in &edges {
+        let label = match action {
+            Some(action) => format!("{:?}", Action::ALL[*action]),
+            None => "goal reached".to_string(),
+        };
+        output += &format!(
+            "    \"{:?}\" -> \"{:?}\" [label=\"{label} ({count})\", penwidth={:.2}];\n",
+            States::ALL[*from],
+            States::ALL[*to],
+            1.0 + 4.0 * *count as f64 / most
+        );
+    }
+    output += "}\n";
+    output
+}
+
+// Position of the outcome in States::ALL
+fn outcome_index(outcome: States) -> usize {
+    States::ALL.iter().position(|s| *s == outcome).unwrap()
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fmt::Display};
+
+use robotics_lib::world::tile::Content;
+use serde::{Deserialize, Serialize};
+
+// Coins the robot has to deposit in a world of the given size when no other goal is set
+pub fn coins_to_deposit(world_size: usize) -> usize {
+    ((world_size * world_size) as f32 * 0.002) as usize
+}
+
+// The contents the robot can put in its backpack
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Collectable {
+    Rock,
+    Tree,
+    Garbage,
+    Coin,
+    Fish,
+}
+
+impl Collectable {
+    pub const ALL: [Collectable; 5] = [
+        Collectable::Rock,
+        Collectable::Tree,
+        Collectable::Garbage,
+        Collectable::Coin,
+        Collectable::Fish,
+    ];
+
+    // The key of the content in the backpack
+    pub fn content(&self) -> Content {
+        match self {
+            Collectable::Rock => Content::Rock(0),
+            Collectable::Tree => Content::Tree(0),
+            Collectable::Garbage => Content::Garbage(0),
+            Collectable::Coin => Content::Coin(0),
+            Collectable::Fish => Content::Fish(0),
+        }
+    }
+
+    // The collectable of a content, None for the ones that cannot go in the backpack
+    pub fn of_content(content: &Content) -> Option<Self> {
+        match content {
+            Content::Rock(_) => Some(Collectable::Rock),
+            Content::Tree(_) => Some(Collectable::Tree),
+            Content::Garbage(_) => Some(Collectable::Garbage),
+            Content::Coin(_) => Some(Collectable::Coin),
+            Content::Fish(_) => Some(Collectable::Fish),
+            _ => None,
+        }
+    }
+}
+
+// What the robot has done so far in the episode, updated every tick
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoalProgress {
+    pub coins_deposited: usize,
+    pub collected: HashMap<Collectable, usize>,
+    // Fraction of the map the robot knows, between 0 and 1
+    pub explored: f64,
+    pub garbage_recycled: usize,
+}
+
+// The mission of the robot, the episode ends when it is reached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Goal {
+    DepositCoins(usize),
+    Collect(Collectable, usize),
+    // Percentage of the map, between 0 and 100
+    Explore(f64),
+    RecycleGarbage(usize),
+    // Reached when all of them are
+    All(Vec<Goal>),
+}
+
+impl Goal {
+    // The goal of the default robot
+    pub fn for_world(world_size: usize) -> Self {
+        Goal::DepositCoins(coins_to_deposit(world_size))
+    }
+
+    // How close the robot is to the goal, between 0 and 1
+    pub fn completion(&self, progress: &GoalProgress) -> f64 {
+        let ratio = |done: f64, needed: f64| {
+            if needed <= 0.0 {
+                return 1.0;
+            }
+            f64::min(done / needed, 1.0)
+        };
+
+        match self {
+            Goal::DepositCoins(n) => ratio(progress.coins_deposited as f64, *n as f64),
+            Goal::Collect(item, n) => ratio(
+                *progress.collected.get(item).unwrap_or(&0) as f64,
+                *n as f64,
+            ),
+            Goal::Explore(percentage) => ratio(progress.explored * 100.0, *percentage),
+            Goal::RecycleGarbage(n) => ratio(progress.garbage_recycled as f64, *n as f64),
+            Goal::All(goals) => {
+                if goals.is_empty() {
+                    return 1.0;
+                }
+                goals.iter().map(|g| g.completion(progress)).sum::<f64>() / goals.len() as f64
+            }
+        }
+    }
+
+    pub fn is_reached(&self, progress: &GoalProgress) -> bool {
+        self.completion(progress) >= 1.0
+    }
+
+    // Coins the robot has to get hold of to reach the goal, the world must have at least as many
+    pub fn coins_needed(&self) -> usize {
+        match self {
+            Goal::DepositCoins(n) | Goal::Collect(Collectable::Coin, n) => *n,
+            Goal::All(goals) => goals.iter().map(Goal::coins_needed).sum(),
+            _ => 0,
+        }
+    }
+
+    // What has been done toward the goal, e.g. "12/20" coins deposited or "35%" of the map explored
+    pub fn progress(&self, progress: &GoalProgress) -> String {
+        match self {
+            Goal::DepositCoins(n) => format!("{}/{n}", progress.coins_deposited),
+            Goal::Collect(item, n) => {
+                format!("{}/{n}", progress.collected.get(item).unwrap_or(&0))
+            }
+            Goal::Explore(_) => format!("{:.0}%", progress.explored * 100.0),
+            Goal::RecycleGarbage(n) => format!("{}/{n}", progress.garbage_recycled),
+            Goal::All(goals) => {
+                let goals: Vec<String> = goals.iter().map(|g| g.progress(progress)).collect();
+                goals.join(", ")
+            }
+        }
+    }
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Goal::DepositCoins(n) => write!(f, "Deposit {n} coins"),
+            Goal::Collect(item, n) => write!(f, "Collect {n} {item:?}"),
+            Goal::Explore(percentage) => write!(f, "Explore {percentage}% of the map"),
+            Goal::RecycleGarbage(n) => write!(f, "Recycle {n} garbage"),
+            Goal::All(goals) => {
+                let goals: Vec<String> = goals.iter().map(|g| g.to_string()).collect();
+                write!(f, "{}", goals.join(" and "))
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::config::{TrainingConfig, ValueFunction};
+use super::linear::{load_weights_from, weights_path, LinearQ, WEIGHTS};
+use super::replay::ReplayLearner;
+use super::state::{State, StateKey};
+use super::utils::{
+    blank_q_table, load_q_table_from, load_q_table_pair_from, q_table_path, write_q_table_pair_to,
+    write_q_table_to, Action, ActionMask, QTable,
+};
+
+// The algorithms the robot can learn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Algorithm {
+    #[default]
+    QLearning,
+    Sarsa,
+    ExpectedSarsa,
+    DoubleQLearning,
+}
+
+// What happened in a tick: in state the robot executed action, got reward and ended up in next_state,
+// where it is going to execute next_action, chosen among the ones in next_mask
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub state: State,
+    pub action: Action,
+    pub reward: f64,
+    pub next_state: State,
+    pub next_mask: ActionMask,
+    pub next_action: Action,
+}
+
+// A reinforcement learning algorithm working over State and Action
+pub trait Learner {
+    // Estimated value of executing action in state
+    fn value(&self, state: State, action: Action) -> f64;
+
+    // Learns from a transition, epsilon is the exploration rate of the policy that generated it.
+    // Returns the TD error of the transition before the update
+    fn update(&mut self, transition: &Transition, epsilon: f64) -> f64 {
+        self.update_weighted(transition, epsilon, 1.0)
+    }
+
+    // Like update, with the learning rate scaled by weight, which replay uses to correct its sampling bias
+    fn update_weighted(&mut self, transition: &Transition, epsilon: f64, weight: f64) -> f64;
+
+    // Writes what has been learned to file
+    fn save(&self, path: &str) -> Result<(), String>;
+
+    // Restarts the random generator of the learner, if it has one
+    fn reseed(&mut self, _seed: u64) {}
+
+    // Action with the highest value in the given state, among the ones the mask allows
+    fn greedy_action(&self, state: State, mask: ActionMask) -> Action {
+        mask.actions()
+            .into_iter()
+            .max_by(|&a1, &a2| {
+                self.value(state, a1)
+                    .partial_cmp(&self.value(state, a2))
+                    .unwrap()
+            })
+            .unwrap()
+    }
+
+    // Epsilon-greedy selection among the allowed actions: a random one with probability epsilon, the greedy one otherwise
+    fn select_action(
+        &self,
+        state: State,
+        mask: ActionMask,
+        epsilon: f64,
+        rng: &mut StdRng,
+    ) -> Action {
+        if rng.gen::<f64>() < epsilon {
+            return *mask.actions().choose(rng).unwrap();
+        }
+        self.greedy_action(state, mask)
+    }
+
+    // The learned values as a Q-table, each key valued in a typical state of its own
+    fn q_table(&self) -> QTable {
+        let mut q_table = QTable::new();
+        for key in StateKey::all() {
+            for action in Action::ALL {
+                q_table.insert((key, action), self.value(State::of_key(key), action));
+            }
+        }
+        q_table
+    }
+}
+
+// Builds the learner selected in the config, starting from the given Q-table
+pub fn new_learner(config: &TrainingConfig, q_table: QTable, seed: u64) -> Box<dyn Learner> {
+    let second = q_table.clone();
+    table_learner(config, q_table, second, seed)
+}
+
+// Builds the learner selected in the config, Double Q-learning starts its two tables from q_table and second
+fn table_learner(
+    config: &TrainingConfig,
+    q_table: QTable,
+    second: QTable,
+    seed: u64,
+) -> Box<dyn Learner> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let alpha = config.learning_rate;
+    let gamma = config.discount;
+    let learner: Box<dyn Learner> = match config.algorithm {
+        Algorithm::QLearning => Box::new(QLearning {
+            q_table,
+            alpha,
+            gamma,
+        }),
+        Algorithm::Sarsa => Box::new(Sarsa {
+            q_table,
+            alpha,
+            gamma,
+        }),
+        Algorithm::ExpectedSarsa => Box::new(ExpectedSarsa {
+            q_table,
+            alpha,
+            gamma,
+        }),
+        Algorithm::DoubleQLearning => Box::new(DoubleQLearning {
+            q_table_a: q_table,
+            q_table_b: second,
+            alpha,
+            gamma,
+            rng: StdRng::seed_from_u64(rng.gen::<u64>()),
+        }),
+    };
+    with_replay(config, learner, &mut rng)
+}
+
+// Wraps the learner in a replay buffer, if the config has one
+fn with_replay(
+    config: &TrainingConfig,
+    learner: Box<dyn Learner>,
+    rng: &mut StdRng,
+) -> Box<dyn Learner> {
+    match &config.replay {
+        Some(replay) => Box::new(ReplayLearner::new(
+            learner,
+            replay.clone(),
+            StdRng::seed_from_u64(rng.gen::<u64>()),
+        )),
+        None => learner,
+    }
+}
+
+// Builds the learner of the config from what the default or the custom robot learned so far
+pub fn load_learner(
+    config: &TrainingConfig,
+    default_rewards: bool,
+    seed: u64,
+) -> Result<Box<dyn Learner>, String> {
+    load_learner_from(config, Some(&learner_path(config, default_rewards)), seed)
+}
+
+// Builds the learner of the config from the file at path, None for a robot that has never been trained
+pub fn load_learner_from(
+    config: &TrainingConfig,
+    path: Option<&str>,
+    seed: u64,
+) -> Result<Box<dyn Learner>, String> {
+    match config.value_function {
+        ValueFunction::Table => {
+            let (q_table, second) = match path {
+                Some(path) if config.algorithm == Algorithm::DoubleQLearning => {
+                    load_q_table_pair_from(path).map_err(|e| format!("Q-Table loading: {e}"))?
+                }
+                Some(path) => {
+                    let q_table =
+                        load_q_table_from(path).map_err(|e| format!("Q-Table loading: {e}"))?;
+                    (q_table.clone(), q_table)
+                }
+                None => (blank_q_table(), blank_q_table()),
+            };
+            Ok(table_learner(config, q_table, second, seed))
+        }
+        ValueFunction::Linear => {
+            let weights = match path {
+                Some(path) => {
+                    load_weights_from(path).map_err(|e| format!("Weights loading: {e}"))?
+                }
+                None => vec![0.0; WEIGHTS],
+            };
+            let learner = Box::new(LinearQ::new(config, weights)?);
+            Ok(with_replay(
+                config,
+                learner,
+                &mut StdRng::seed_from_u64(seed),
+            ))
+        }
+    }
+}
+
+// File where the learner of the config is saved
+pub fn learner_path(config: &TrainingConfig, default_rewards: bool) -> String {
+    match config.value_function {
+        ValueFunction::Table => q_table_path(default_rewards),
+        ValueFunction::Linear => weights_path(default_rewards),
+    }
+}
+
+// Highest value of a state in a Q-table, among the allowed actions
+fn max_value(q_table: &QTable, state: StateKey, mask: ActionMask) -> f64 {
+    mask.actions()
+        .iter()
+        .map(|a| q_table[&(state, *a)])
+        .fold(f64::MIN, f64::max)
+}
+
+// Moves Q(s, a) toward reward + gamma * target, returns the TD error
+fn td_update(q_table: &mut QTable, t: &Transition, target: f64, alpha: f64, gamma: f64) -> f64 {
+    let q_value = q_table.get_mut(&(t.state.key, t.action)).unwrap();
+    let error = t.reward + gamma * target - *q_value;
+    *q_value += alpha * error;
+    error
+}
+
+// Off-policy: bootstraps from the best action of the next state
+pub struct QLearning {
+    q_table: QTable,
+    alpha: f64,
+    gamma: f64,
+}
+
+impl Learner for QLearning {
+    fn value(&self, state: State, action: Action) -> f64 {
+        self.q_table[&(state.key, action)]
+    }
+
+    fn update_weighted(&mut self, transition: &Transition, _epsilon: f64, weight: f64) -> f64 {
+        let target = max_value(
+            &self.q_table,
+            transition.next_state.key,
+            transition.next_mask,
+        );
+        td_update(
+            &mut self.q_table,
+            transition,
+            target,
+            self.alpha * weight,
+            self.gamma,
+        )
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        write_q_table_to(path, &self.q_table)
+    }
+}
+
+// On-policy: bootstraps from the action that is actually going to be executed
+pub struct Sarsa {
+    q_table: QTable,
+    alpha: f64,
+    gamma: f64,
+}
+
+impl Learner for Sarsa {
+    fn value(&self, state: State, action: Action) -> f64 {
+        self.q_table[&(state.key, action)]
+    }
+
+    fn update_weighted(&mut self, transition: &Transition, _epsilon: f64, weight: f64) -> f64 {
+        let target = self.q_table[&(transition.next_state.key, transition.next_action)];
+        td_update(
+            &mut self.q_table,
+            transition,
+            target,
+            self.alpha * weight,
+            self.gamma,
+        )
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        write_q_table_to(path, &self.q_table)
+    }
+}
+
+// Bootstraps from the expected value of the next state under the epsilon-greedy policy
+pub struct ExpectedSarsa {
+    q_table: QTable,
+    alpha: f64,
+    gamma: f64,
+}
+
+impl Learner for ExpectedSarsa {
+    fn value(&self, state: State, action: Action) -> f64 {
+        self.q_table[&(state.key, action)]
+    }
+
+    fn update_weighted(&mut self, transition: &Transition, epsilon: f64, weight: f64) -> f64 {
+        let next_state = transition.next_state.key;
+        let allowed = transition.next_mask.actions();
+        let mean = allowed
+            .iter()
+            .map(|a| self.q_table[&(next_state, *a)])
+            .sum::<f64>()
+            / allowed.len() as f64;
+        let target = (1.0 - epsilon) * max_value(&self.q_table, next_state, transition.next_mask)
+            + epsilon * mean;
+        td_update(
+            &mut self.q_table,
+            transition,
+            target,
+            self.alpha * weight,
+            self.gamma,
+        )
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        write_q_table_to(path, &self.q_table)
+    }
+}
+
+// Two tables, one picks the best next action and the other evaluates it, to reduce the overestimation of Q-learning.
+// The robot acts on the average of the two, both are saved so that the training can continue from them
+pub struct DoubleQLearning {
+    q_table_a: QTable,
+    q_table_b: QTable,
+    alpha: f64,
+    gamma: f64,
+    rng: StdRng,
+}
+
+impl Learner for DoubleQLearning {
+    fn value(&self, state: State, action: Action) -> f64 {
+        (self.q_table_a[&(state.key, action)] + self.q_table_b[&(state.key, action)]) / 2.0
+    }
+
+    fn update_weighted(&mut self, transition: &Transition, _epsilon: f64, weight: f64) -> f64 {
+        // Half of the times each table is updated
+        let (to_update, evaluator) = if self.rng.gen::<bool>() {
+            (&mut self.q_table_a, &self.q_table_b)
+        } else {
+            (&mut self.q_table_b, &self.q_table_a)
+        };
+
+        let next_state = transition.next_state.key;
+        let best_next = transition
+            .next_mask
+            .actions()
+            .into_iter()
+            .max_by(|&a1, &a2| {
+                to_update[&(next_state, a1)]
+                    .partial_cmp(&to_update[&(next_state, a2)])
+                    .unwrap()
+            })
+            .unwrap();
+        let target = evaluator[&(next_state, best_next)];
+        td_update(
+            to_update,
+            transition,
+            target,
+            self.alpha * weight,
+            self.gamma,
+        )
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        write_q_table_pair_to(path, &self.q_table_a, &self.q_table_b)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::config::TrainingConfig;
+use super::learner::{Algorithm, Learner, Transition};
+use super::state::State;
+use super::utils::{Action, ActionMask, States};
+
+// Version of the weights file format, increased every time the features change.
+// Version 2 added the Rest action and the Rested outcome, version 3 replaced the levels of the key with continuous features
+pub const WEIGHTS_VERSION: u32 = 3;
+
+// Features of a state: bias, one-hot outcome, energy, backpack fill, coins, distance to the nearest bank and market,
+// market known, bank known
+pub const STATE_FEATURES: usize = 1 + States::ALL.len() + 7;
+
+// Every action has its own block of weights over the state features
+pub const WEIGHTS: usize = STATE_FEATURES * Action::ALL.len();
+
+// Weight vector as it is written on file
+#[derive(Serialize, Deserialize)]
+struct WeightsFile {
+    version: u32,
+    weights: Vec<f64>,
+}
+
+// Hand-crafted features of a state: the outcome of the key and what the robot observes, as continuous values
+pub fn features(state: State) -> [f64; STATE_FEATURES] {
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+
+    let mut features = [0.0; STATE_FEATURES];
+    features[0] = 1.0;
+
+    let outcome = States::ALL
+        .iter()
+        .position(|s| *s == state.key.outcome)
+        .unwrap();
+    features[1 + outcome] = 1.0;
+
+    let rest = 1 + States::ALL.len();
+    features[rest] = state.features.energy;
+    features[rest + 1] = state.features.backpack_fill;
+    features[rest + 2] = state.features.coins;
+    features[rest + 3] = state.features.bank_distance;
+    features[rest + 4] = state.features.market_distance;
+    features[rest + 5] = flag(state.key.market_known);
+    features[rest + 6] = flag(state.key.bank_known);
+    features
+}
+
+// Index of the first weight of the action's block
+fn block(action: Action) -> usize {
+    action.index() * STATE_FEATURES
+}
+
+// Q(s, a) approximated as the dot product of the state features with the weights of the action,
+// trained with semi-gradient TD. The bootstrap target follows the algorithm of the config
+pub struct LinearQ {
+    weights: Vec<f64>,
+    algorithm: Algorithm,
+    alpha: f64,
+    gamma: f64,
+}
+
+impl LinearQ {
+    pub fn new(config: &TrainingConfig, weights: Vec<f64>) -> Result<Self, String> {
+        if config.algorithm == Algorithm::DoubleQLearning {
+            return Err("DoubleQLearning is only available with the Q-table".to_string());
+        }
+        if weights.len() != WEIGHTS {
+            return Err(format!(
+                "Expected {WEIGHTS} weights, found {}",
+                weights.len()
+            ));
+        }
+
+        Ok(LinearQ {
+            weights,
+            algorithm: config.algorithm,
+            alpha: config.learning_rate,
+            gamma: config.discount,
+        })
+    }
+
+    fn max_value(&self, state: State, mask: ActionMask) -> f64 {
+        mask.actions()
+            .iter()
+            .map(|a| self.value(state, *a))
+            .fold(f64::MIN, f64::max)
+    }
+}
+
+impl Learner for LinearQ {
+    fn value(&self, state: State, action: Action) -> f64 {
+        let start = block(action);
+        features(state)
+            .iter()
+            .zip(&self.weights[start..start + STATE_FEATURES])
+            .map(|(f, w)| f * w)
+            .sum()
+    }
+
+    fn update_weighted(&mut self, transition: &Transition, epsilon: f64, weight: f64) -> f64 {
+        let next_state = transition.next_state;
+        let next_mask = transition.next_mask;
+        let target = match self.algorithm {
+            Algorithm::Sarsa => self.value(next_state, transition.next_action),
+            Algorithm::ExpectedSarsa => {
+                let allowed = next_mask.actions();
+                let mean = allowed
+                    .iter()
+                    .map(|a| self.value(next_state, *a))
+                    .sum::<f64>()
+                    / allowed.len() as f64;
+                (1.0 - epsilon) * self.max_value(next_state, next_mask) + epsilon * mean
+            }
+            _ => self.max_value(next_state, next_mask),
+        };
+
+        let error = transition.reward + self.gamma * target
+            - self.value(transition.state, transition.action);
+
+        // The gradient of a linear function is the feature vector itself
+        let start = block(transition.action);
+        for (w, f) in self.weights[start..start + STATE_FEATURES]
+            .iter_mut()
+            .zip(features(transition.state))
+        {
+            *w += self.alpha * weight * error * f;
+        }
+        error
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        write_weights_to(path, &self.weights)
+    }
+}
+
+// Path of the weights of the default or of the custom robot
+pub fn weights_path(default_rewards: bool) -> String {
+    if default_rewards {
+        return "q_weights.txt".to_string();
+    }
+    "custom_q_weights.txt".to_string()
+}
+
+// Loads the weights from the given path, a missing file means a robot that has never been trained
+pub fn load_weights_from(path: &str) -> Result<Vec<f64>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![0.0; WEIGHTS]),
+        Err(e) => return Err(format!("File open: {e}")),
+    };
+
+    let file: WeightsFile =
+        ron::from_str(&content).map_err(|e| format!("{path} is not a valid weights file: {e}"))?;
+
+    if file.version != WEIGHTS_VERSION {
+        return Err(format!(
+            "{path} has version {}, expected {WEIGHTS_VERSION}, it must be trained again",
+            file.version
+        ));
+    }
+    if file.weights.len() != WEIGHTS {
+        return Err(format!(
+            "{path} has {} weights, expected {WEIGHTS}",
+            file.weights.len()
+        ));
+    }
+    if let Some(w) = file.weights.iter().find(|w| !w.is_finite()) {
+        return Err(format!("Invalid weight {w} in {path}"));
+    }
+
+    Ok(file.weights)
+}
+
+// Writes the weights to the given path, in the same RON format read by load_weights_from
+pub fn write_weights_to(path: &str, weights: &[f64]) -> Result<(), String> {
+    let file = WeightsFile {
+        version: WEIGHTS_VERSION,
+        weights: weights.to_vec(),
+    };
+    let serialized = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .map_err(|e| format!("Serializing: {e}"))?;
+
+    let mut output = File::create(path).map_err(|e| format!("File create: {e}"))?;
+    output
+        .write_all(serialized.as_bytes())
+        .map_err(|e| format!("File write: {e}"))
+}
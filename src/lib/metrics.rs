@@ -0,0 +1,147 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::ai::{EpisodeSummary, TickRecord};
+use super::utils::Action;
+
+// Where the training metrics are written, ticks_path is optional since it grows with every tick played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub episodes_path: String,
+    pub ticks_path: Option<String>,
+}
+
+// Writes one CSV row per episode and, if requested, one per tick. Rows are flushed at the end of
+// every episode, so an interrupted training keeps the episodes already played
+pub struct MetricsWriter {
+    episodes: BufWriter<File>,
+    ticks: Option<BufWriter<File>>,
+}
+
+impl MetricsWriter {
+    // Creates the files and writes their headers
+    pub fn create(config: &MetricsConfig) -> Result<Self, String> {
+        let actions: Vec<String> = Action::ALL.iter().map(|a| format!("{a:?}")).collect();
+
+        let mut episodes = create_csv(&config.episodes_path)?;
+        writeln!(
+            episodes,
+            "episode,status,ticks,total_reward,epsilon,energy_spent,coins_deposited,tiles_discovered,max_q_change,mean_q_change,policy_changes,{}",
+            actions.join(",")
+        )
+        .map_err(|e| format!("File write: {e}"))?;
+
+        let ticks = match &config.ticks_path {
+            Some(path) => {
+                let mut ticks = create_csv(path)?;
+                writeln!(
+                    ticks,
+                    "episode,tick,action,reward,epsilon,energy_consumed,coins_deposited,tiles_discovered"
+                )
+                .map_err(|e| format!("File write: {e}"))?;
+                Some(ticks)
+            }
+            None => None,
+        };
+
+        Ok(MetricsWriter { episodes, ticks })
+    }
+
+    // Opens the files again for a resumed session: the episodes file is written from the summaries of the
+    // checkpoint, and the ticks file keeps only the rows of those episodes, the later ones are played again
+    pub fn resume(config: &MetricsConfig, summaries: &[EpisodeSummary]) -> Result<Self, String> {
+        let kept: Vec<String> = match &config.ticks_path {
+            Some(path) => fs::read_to_string(path)
+                .unwrap_or_default()
+                .lines()
+                .skip(1)
+                .filter(|line| {
+                    let episode = line.split(',').next().and_then(|e| e.parse::<usize>().ok());
+                    matches!(episode, Some(episode) if episode <= summaries.len())
+                })
+                .map(str::to_string)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut writer = MetricsWriter::create(config)?;
+        for (i, summary) in summaries.iter().enumerate() {
+            writer.log_episode(i + 1, summary, &[])?;
+        }
+        if let Some(ticks) = &mut writer.ticks {
+            for line in kept {
+                writeln!(ticks, "{line}").map_err(|e| format!("File write: {e}"))?;
+            }
+            ticks.flush().map_err(|e| format!("File write: {e}"))?;
+        }
+        Ok(writer)
+    }
+
+    // Appends the rows of an episode, numbered from 1
+    pub fn log_episode(
+        &mut self,
+        episode: usize,
+        summary: &EpisodeSummary,
+        tick_log: &[TickRecord],
+    ) -> Result<(), String> {
+        let histogram: Vec<String> = summary
+            .action_counts
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        // Left empty when the change was not measured
+        let q_change = match summary.q_change {
+            Some(change) => format!(
+                "{},{},{}",
+                change.max_delta, change.mean_delta, change.policy_changes
+            ),
+            None => ",,".to_string(),
+        };
+        writeln!(
+            self.episodes,
+            "{episode},{:?},{},{},{},{},{},{},{},{}",
+            summary.status,
+            summary.ticks,
+            summary.total_reward,
+            summary.epsilon,
+            summary.energy_spent,
+            summary.coins_deposited,
+            summary.tiles_discovered,
+            q_change,
+            histogram.join(",")
+        )
+        .map_err(|e| format!("File write: {e}"))?;
+        self.episodes
+            .flush()
+            .map_err(|e| format!("File write: {e}"))?;
+
+        if let Some(ticks) = &mut self.ticks {
+            for record in tick_log {
+                writeln!(
+                    ticks,
+                    "{episode},{},{:?},{},{},{},{},{}",
+                    record.tick,
+                    record.action,
+                    record.reward,
+                    record.epsilon,
+                    record.energy_consumed,
+                    record.coins_deposited,
+                    record.tiles_discovered
+                )
+                .map_err(|e| format!("File write: {e}"))?;
+            }
+            ticks.flush().map_err(|e| format!("File write: {e}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn create_csv(path: &str) -> Result<BufWriter<File>, String> {
+    let file = File::create(path).map_err(|e| format!("File create: {e}"))?;
+    Ok(BufWriter::new(file))
+}
@@ -4,6 +4,21 @@
 pub mod actions;
 // pub mod ai_main;
 pub mod ai;
+pub mod checkpoint;
+pub mod config;
+pub mod convergence;
+pub mod curriculum;
 pub mod data_storage;
+pub mod export;
+pub mod goal;
+pub mod learner;
+pub mod linear;
+pub mod metrics;
 pub mod my_events;
+pub mod parallel;
+pub mod replay;
+pub mod reward;
+pub mod state;
+pub mod sweep;
+pub mod training;
 pub mod utils;
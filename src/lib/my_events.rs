@@ -3,10 +3,12 @@ use robotics_lib::{
     world::tile::{Content, Tile},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum MyEvents2 {
     Event(Event),
     RobotSpawned((usize, usize)),
     UsedTool(Vec<Vec<Option<Tile>>>),
     ContentInteracted(Content, (usize, usize)),
+    // The robot waited for its energy to recharge, one or more ticks in a row
+    Rested,
 }
@@ -0,0 +1,233 @@
+use std::{collections::HashMap, thread};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::ai::{run_episode, EpisodeSummary, TickRecord};
+use super::checkpoint::Checkpoint;
+use super::config::{Mode, ValueFunction};
+use super::convergence::ConvergenceTracker;
+use super::learner::new_learner;
+use super::state::StateKey;
+use super::training::{
+    create_metrics, report_episode, write_checkpoint, StopReason, TrainerConfig, TrainingReport,
+};
+use super::utils::{blank_q_table, load_q_table_from, write_q_table_to, Action, QTable};
+
+// How the tables of the workers are combined into the shared one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Merge {
+    // Plain mean of the workers' values
+    #[default]
+    Average,
+    // Each worker counts as many times as it executed the action in the state,
+    // values nobody visited are left as they were
+    VisitWeighted,
+}
+
+// K workers, each playing sync_every episodes on its own world before the tables are merged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParallelConfig {
+    pub workers: usize,
+    pub sync_every: usize,
+    pub merge: Merge,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        ParallelConfig {
+            workers: 4,
+            sync_every: 1,
+            merge: Merge::Average,
+        }
+    }
+}
+
+// What a worker learned in a round, with the summary and the ticks of each episode it played
+struct WorkerOutput {
+    q_table: QTable,
+    visits: HashMap<(StateKey, Action), usize>,
+    episodes: Vec<(EpisodeSummary, Vec<TickRecord>)>,
+}
+
+// Like train(), but every round the workers start from the shared table, each one on its own thread and world,
+// and their tables are merged at the end of the round. Only available with the Q-table value function.
+// The change of an episode is measured on the table of its worker, convergence ends the training after the round
+pub fn train_parallel(
+    episodes: usize,
+    config: &TrainerConfig,
+    parallel: &ParallelConfig,
+) -> Result<TrainingReport, String> {
+    config.validate()?;
+    if config.training.value_function != ValueFunction::Table {
+        return Err("Parallel training is only available with the Q-table".to_string());
+    }
+    if parallel.workers == 0 || parallel.sync_every == 0 {
+        return Err(
+            "Parallel training needs at least one worker and one episode per round".to_string(),
+        );
+    }
+
+    let mut session = match config.resumed(episodes)? {
+        Some(session) => session,
+        None => Checkpoint::new(
+            config,
+            episodes,
+            StdRng::seed_from_u64(config.seed).gen::<u64>(),
+        ),
+    };
+    let mut shared = if config.resume {
+        load_q_table_from(&session.learner).map_err(|e| format!("Q-Table loading: {e}"))?
+    } else if config.blank {
+        blank_q_table()
+    } else {
+        load_q_table_from(&config.input_path()).map_err(|e| format!("Q-Table loading: {e}"))?
+    };
+    let path = config.output_path();
+    let mut rng = StdRng::seed_from_u64(session.rng_seed);
+    let mut metrics = create_metrics(config, &session.summaries)?;
+    let mut convergence =
+        ConvergenceTracker::resume(config.training.convergence.clone(), session.stable_episodes);
+    let mut stop = StopReason::Completed;
+
+    while session.summaries.len() < episodes {
+        // The episodes left are split among the workers, at most sync_every each
+        let round = usize::min(
+            episodes - session.summaries.len(),
+            parallel.workers * parallel.sync_every,
+        );
+        let assignments: Vec<(usize, u64)> = (0..parallel.workers)
+            .map(|k| {
+                let n = round / parallel.workers + usize::from(k < round % parallel.workers);
+                (n, rng.gen::<u64>())
+            })
+            .filter(|(n, _)| *n > 0)
+            .collect();
+
+        let ticks_played = session.ticks_played;
+        let outputs = thread::scope(|scope| {
+            let handles: Vec<_> = assignments
+                .iter()
+                .map(|&(n, seed)| {
+                    let q_table = shared.clone();
+                    scope.spawn(move || run_worker(config, q_table, n, seed, ticks_played))
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| {
+                    h.join()
+                        .map_err(|_| "A training worker panicked".to_string())
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })?;
+
+        shared = merge(&shared, &outputs, parallel.merge);
+
+        for output in outputs {
+            for (summary, tick_log) in output.episodes {
+                let episode = session.summaries.len() + 1;
+                session.ticks_played += summary.ticks;
+                report_episode(episode, Some(episodes), &summary, &tick_log, &mut metrics)?;
+                if convergence.observe(summary.q_change) && stop == StopReason::Completed {
+                    stop = StopReason::Converged { episode };
+                }
+                session.summaries.push(summary);
+            }
+        }
+        session.stable_episodes = convergence.stable_episodes();
+        session.rng_seed = rng.gen::<u64>();
+        rng = StdRng::seed_from_u64(session.rng_seed);
+        if stop != StopReason::Completed {
+            break;
+        }
+
+        // Intermediate checkpoint, the last round is written below anyway
+        if let Some(every) = config.checkpoint_every.filter(|every| *every > 0) {
+            let played = session.summaries.len();
+            let before = played - round;
+            if played / every > before / every && played < episodes {
+                write_q_table_to(&path, &shared).map_err(|e| format!("Q-Table writing: {e}"))?;
+                write_checkpoint(config, &mut session, |file| write_q_table_to(file, &shared))?;
+            }
+        }
+    }
+
+    write_q_table_to(&path, &shared).map_err(|e| format!("Q-Table writing: {e}"))?;
+    write_checkpoint(config, &mut session, |file| write_q_table_to(file, &shared))?;
+
+    Ok(TrainingReport {
+        summaries: session.summaries,
+        stop,
+    })
+}
+
+// Plays the given number of episodes starting from a copy of the shared table
+fn run_worker(
+    config: &TrainerConfig,
+    q_table: QTable,
+    episodes: usize,
+    seed: u64,
+    first_tick: usize,
+) -> WorkerOutput {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut learner = new_learner(&config.training, q_table, rng.gen::<u64>());
+    let mut visits = HashMap::new();
+    let mut played = Vec::with_capacity(episodes);
+    let mut ticks_played = first_tick;
+
+    for _ in 0..episodes {
+        let result = run_episode(
+            config.world_size,
+            &config.generator,
+            &config.rewards,
+            learner.as_mut(),
+            &config.training,
+            ticks_played,
+            rng.gen::<u64>(),
+            &mut rng,
+            Mode::Training,
+        );
+        ticks_played += result.summary.ticks;
+        for record in &result.tick_log {
+            *visits.entry((record.state, record.action)).or_insert(0) += 1;
+        }
+        // The maps and the events of the episode are only needed by the visualizers
+        played.push((result.summary, result.tick_log));
+    }
+
+    WorkerOutput {
+        q_table: learner.q_table(),
+        visits,
+        episodes: played,
+    }
+}
+
+// Combines the tables of the workers, cell by cell
+fn merge(shared: &QTable, outputs: &[WorkerOutput], merge: Merge) -> QTable {
+    let mut merged = QTable::new();
+    for (key, shared_value) in shared {
+        let value = match merge {
+            Merge::Average => {
+                outputs.iter().map(|o| o.q_table[key]).sum::<f64>() / outputs.len() as f64
+            }
+            Merge::VisitWeighted => {
+                let visits = |o: &WorkerOutput| *o.visits.get(key).unwrap_or(&0) as f64;
+                let total: f64 = outputs.iter().map(visits).sum();
+                if total == 0.0 {
+                    *shared_value
+                } else {
+                    outputs
+                        .iter()
+                        .map(|o| o.q_table[key] * visits(o))
+                        .sum::<f64>()
+                        / total
+                }
+            }
+        };
+        merged.insert(*key, value);
+    }
+    merged
+}
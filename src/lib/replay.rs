@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::learner::{Learner, Transition};
+use super::state::State;
+use super::utils::{Action, QTable};
+
+// Priority of a transition whose TD error is 0, so that it can still be replayed
+const MIN_PRIORITY: f64 = 1e-3;
+
+// How the transitions to replay are picked from the buffer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Sampling {
+    // Every transition has the same probability
+    Uniform,
+    // Proportional to |TD error|^exponent, 0 is uniform and 1 fully proportional.
+    // New transitions get the highest priority, so they are replayed at least once soon.
+    // Replaying some transitions more often biases the values toward them, correction between 0 (none)
+    // and 1 (fully compensated) scales down the updates of the frequent ones by importance sampling
+    Prioritized {
+        exponent: f64,
+        #[serde(default)]
+        correction: f64,
+    },
+}
+
+// A bounded buffer of past transitions, batch_size of them are replayed after every tick.
+// When the buffer is full the oldest transition is dropped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayConfig {
+    pub capacity: usize,
+    pub batch_size: usize,
+    pub sampling: Sampling,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        ReplayConfig {
+            capacity: 10_000,
+            batch_size: 32,
+            sampling: Sampling::Uniform,
+        }
+    }
+}
+
+// A transition as it is stored, with the exploration rate it was generated with
+struct Experience {
+    transition: Transition,
+    epsilon: f64,
+}
+
+// Sampling weights of the slots of the buffer in a binary tree whose nodes hold the sum of their children:
+// the root is the total, and both drawing a slot in proportion to its weight and changing a weight
+// take a number of steps logarithmic in the capacity
+struct SumTree {
+    capacity: usize,
+    // Node 1 is the root, node k has children 2k and 2k + 1, slot i is the leaf capacity + i
+    nodes: Vec<f64>,
+}
+
+impl SumTree {
+    fn new(capacity: usize) -> Self {
+        SumTree {
+            capacity,
+            nodes: vec![0.0; 2 * capacity],
+        }
+    }
+
+    fn total(&self) -> f64 {
+        self.nodes[1]
+    }
+
+    fn weight(&self, slot: usize) -> f64 {
+        self.nodes[self.capacity + slot]
+    }
+
+    fn set(&mut self, slot: usize, weight: f64) {
+        let mut node = self.capacity + slot;
+        self.nodes[node] = weight;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node] + self.nodes[2 * node + 1];
+        }
+    }
+
+    // The slot where the cumulative weight, in the order of the leaves, goes past mass
+    fn find(&self, mut mass: f64) -> usize {
+        let mut node = 1;
+        while node < self.capacity {
+            let left = 2 * node;
+            if mass < self.nodes[left] {
+                node = left;
+            } else {
+                mass -= self.nodes[left];
+                node = left + 1;
+            }
+        }
+        node - self.capacity
+    }
+}
+
+// Learns from every transition as it happens, like the learner it wraps, then keeps it in the buffer and
+// learns again from a mini-batch of the stored ones. The buffer lives as long as the learner, across episodes
+pub struct ReplayLearner {
+    learner: Box<dyn Learner>,
+    config: ReplayConfig,
+    // Used as a ring: once full, next is the oldest transition, the one replaced by the next push
+    buffer: Vec<Experience>,
+    next: usize,
+    priorities: SumTree,
+    // Highest priority given so far, the one of new transitions
+    max_priority: f64,
+    rng: StdRng,
+}
+
+impl ReplayLearner {
+    pub fn new(learner: Box<dyn Learner>, config: ReplayConfig, rng: StdRng) -> Self {
+        ReplayLearner {
+            learner,
+            buffer: Vec::with_capacity(config.capacity),
+            next: 0,
+            priorities: SumTree::new(config.capacity),
+            max_priority: MIN_PRIORITY,
+            config,
+            rng,
+        }
+    }
+
+    // Priority of a transition with the given TD error. A diverged learner gives errors that are not finite,
+    // those transitions get the highest priority so far instead of breaking the sampling
+    fn priority(&self, error: f64) -> f64 {
+        if !error.is_finite() {
+            return self.max_priority;
+        }
+        f64::max(error.abs(), MIN_PRIORITY)
+    }
+
+    fn set_priority(&mut self, slot: usize, priority: f64) {
+        self.max_priority = f64::max(self.max_priority, priority);
+        if let Sampling::Prioritized { exponent, .. } = self.config.sampling {
+            self.priorities.set(slot, priority.powf(exponent));
+        }
+    }
+
+    // Slots of the transitions to replay, drawn with replacement, each with the weight of its update
+    fn sample(&mut self) -> Vec<(usize, f64)> {
+        let n = self.config.batch_size;
+        let len = self.buffer.len();
+        match self.config.sampling {
+            Sampling::Uniform => (0..n).map(|_| (self.rng.gen_range(0..len), 1.0)).collect(),
+            Sampling::Prioritized { correction, .. } => {
+                let total = self.priorities.total();
+                let slots: Vec<usize> = (0..n)
+                    .map(|_| {
+                        let slot = self.priorities.find(self.rng.gen::<f64>() * total);
+                        // Rounding can land past the last transition while the buffer is filling up
+                        slot.min(len - 1)
+                    })
+                    .collect();
+
+                // Importance sampling: (len * P(i))^-correction, scaled so that the largest weight is 1
+                let weights: Vec<f64> = slots
+                    .iter()
+                    .map(|&slot| {
+                        let probability = self.priorities.weight(slot) / total;
+                        (len as f64 * probability).powf(-correction)
+                    })
+                    .collect();
+                let largest = weights.iter().cloned().fold(f64::MIN_POSITIVE, f64::max);
+                slots
+                    .into_iter()
+                    .zip(weights.into_iter().map(|w| w / largest))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl Learner for ReplayLearner {
+    fn value(&self, state: State, action: Action) -> f64 {
+        self.learner.value(state, action)
+    }
+
+    fn update_weighted(&mut self, transition: &Transition, epsilon: f64, weight: f64) -> f64 {
+        let error = self.learner.update_weighted(transition, epsilon, weight);
+
+        if self.config.capacity == 0 {
+            return error;
+        }
+        let experience = Experience {
+            transition: *transition,
+            epsilon,
+        };
+        let slot = self.next;
+        if self.buffer.len() < self.config.capacity {
+            self.buffer.push(experience);
+        } else {
+            self.buffer[slot] = experience;
+        }
+        self.next = (slot + 1) % self.config.capacity;
+        let priority = f64::max(self.priority(error), self.max_priority);
+        self.set_priority(slot, priority);
+
+        // Nothing is replayed until there is enough experience for a whole batch
+        if self.config.batch_size == 0 || self.buffer.len() < self.config.batch_size {
+            return error;
+        }
+        for (slot, weight) in self.sample() {
+            let experience = &self.buffer[slot];
+            let replayed =
+                self.learner
+                    .update_weighted(&experience.transition, experience.epsilon, weight);
+            let priority = self.priority(replayed);
+            self.set_priority(slot, priority);
+        }
+
+        error
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        self.learner.save(path)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.learner.reseed(self.rng.gen::<u64>());
+    }
+
+    fn q_table(&self) -> QTable {
+        self.learner.q_table()
+    }
+}
@@ -0,0 +1,70 @@
+use std::{fs::File, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use super::ai::REWARDS;
+use super::utils::{Action, States};
+
+// Reward of a tick where nothing in particular happened
+pub const NEUTRAL_REWARD: f64 = -1.0;
+
+// Everything that happened in a tick: in previous the robot executed action and ended up in state
+#[derive(Debug, Clone, Copy)]
+pub struct TickContext {
+    pub previous: States,
+    pub action: Action,
+    pub state: States,
+    // Deltas of the tick
+    pub energy_consumed: usize,
+    pub coins_deposited: usize,
+    pub items_gained: usize,
+}
+
+// Decides how good a tick was for the robot
+pub trait RewardFunction {
+    fn reward(&self, context: &TickContext) -> f64;
+}
+
+// One reward for each state the robot comes from, in the same order of REWARDS:
+// [Start, Goal, Destroyed, Sold, PutInBank, Recycled, NeedsExploring, BackpackFull].
+// Written to file as a plain array, which is the format of rewards.ron
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TableRewards(pub [f64; 8]);
+
+impl Default for TableRewards {
+    fn default() -> Self {
+        TableRewards(REWARDS)
+    }
+}
+
+impl TableRewards {
+    // Loads the rewards from a RON file like rewards.ron
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(|e| format!("File read: {e}"))?;
+
+        ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))
+    }
+}
+
+impl RewardFunction for TableRewards {
+    fn reward(&self, context: &TickContext) -> f64 {
+        let rewards = &self.0;
+        match context.previous {
+            States::Start => rewards[0],
+            States::Goal => rewards[1],
+            States::Destroyed => rewards[2],
+            States::Sold(_) => rewards[3],
+            States::PutInBank(_) => rewards[4],
+            States::Recycled => rewards[5],
+            States::NeedsExploring => rewards[6],
+            States::BackpackFullCoins => rewards[7],
+            States::BackpackFullItems => rewards[7],
+            States::Neutral => NEUTRAL_REWARD,
+            States::Rested => NEUTRAL_REWARD,
+        }
+    }
+}
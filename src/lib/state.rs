@@ -0,0 +1,248 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use robotics_lib::world::tile::Content;
+
+use serde::{Deserialize, Serialize};
+
+use super::goal::Collectable;
+use super::utils::States;
+
+// Energy of a fully charged robot
+pub const MAX_ENERGY: usize = 1000;
+
+// Upper bounds (excluded) of the Low and Medium buckets, as a fraction of the maximum
+const LOW_THRESHOLD: f64 = 1.0 / 3.0;
+const MEDIUM_THRESHOLD: f64 = 2.0 / 3.0;
+
+// Coins held by the robot: Low is none at all, Medium up to COINS_THRESHOLD, High more than that
+const COINS_THRESHOLD: usize = 10;
+
+// What the robot knows about itself and the world at the end of a tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub energy: usize,
+    pub backpack_used: usize,
+    pub backpack_size: usize,
+    pub backpack_contents: HashMap<Content, usize>,
+    pub coins: usize,
+    pub market_known: bool,
+    pub bank_known: bool,
+    // Contents the robot has found and can go and destroy
+    pub known_contents: HashSet<Collectable>,
+    // Tiles of the robot_map that are not None, used to tell whether the robot is still making progress
+    pub discovered_tiles: usize,
+    // Manhattan distance to the nearest bank and market of the ChartedMap, None while none is known
+    pub bank_distance: Option<usize>,
+    pub market_distance: Option<usize>,
+    // Side of the robot_map, the largest distance is twice as much
+    pub map_size: usize,
+}
+
+// A robot that just spawned: full energy, empty backpack and nothing known
+impl Default for Observation {
+    fn default() -> Self {
+        Observation {
+            energy: MAX_ENERGY,
+            backpack_used: 0,
+            backpack_size: 1,
+            backpack_contents: HashMap::new(),
+            coins: 0,
+            market_known: false,
+            bank_known: false,
+            known_contents: HashSet::new(),
+            discovered_tiles: 0,
+            bank_distance: None,
+            market_distance: None,
+            map_size: 1,
+        }
+    }
+}
+
+// Bucket of a continuous feature
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Level {
+    Low,
+    Medium,
+    High,
+}
+
+impl Level {
+    pub const ALL: [Level; 3] = [Level::Low, Level::Medium, Level::High];
+
+    fn from_ratio(ratio: f64) -> Self {
+        if ratio < LOW_THRESHOLD {
+            return Level::Low;
+        }
+        if ratio < MEDIUM_THRESHOLD {
+            return Level::Medium;
+        }
+        Level::High
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Low => write!(f, "L"),
+            Level::Medium => write!(f, "M"),
+            Level::High => write!(f, "H"),
+        }
+    }
+}
+
+// Discrete state used as key of the Q-table: the outcome of the last action plus the bucketed observation
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StateKey {
+    pub outcome: States,
+    pub energy: Level,
+    pub backpack: Level,
+    pub coins: Level,
+    pub market_known: bool,
+    pub bank_known: bool,
+}
+
+impl StateKey {
+    // Buckets the observation, Sold and PutInBank are stored with 0 items like in the Q-table
+    pub fn encode(outcome: States, observation: &Observation) -> Self {
+        let outcome = match outcome {
+            States::Sold(_) => States::Sold(0),
+            States::PutInBank(_) => States::PutInBank(0),
+            s => s,
+        };
+
+        let coins = match observation.coins {
+            0 => Level::Low,
+            n if n <= COINS_THRESHOLD => Level::Medium,
+            _ => Level::High,
+        };
+
+        StateKey {
+            outcome,
+            energy: Level::from_ratio(observation.energy as f64 / MAX_ENERGY as f64),
+            backpack: Level::from_ratio(
+                observation.backpack_used as f64 / observation.backpack_size.max(1) as f64,
+            ),
+            coins,
+            market_known: observation.market_known,
+            bank_known: observation.bank_known,
+        }
+    }
+
+    // Every state key, in a fixed order
+    pub fn all() -> Vec<StateKey> {
+        let mut keys = Vec::new();
+        for outcome in States::ALL {
+            for energy in Level::ALL {
+                for backpack in Level::ALL {
+                    for coins in Level::ALL {
+                        for market_known in [false, true] {
+                            for bank_known in [false, true] {
+                                keys.push(StateKey {
+                                    outcome,
+                                    energy,
+                                    backpack,
+                                    coins,
+                                    market_known,
+                                    bank_known,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        keys
+    }
+}
+
+// Compact form used by the tools, e.g. "NeedsExploring energy:H backpack:L coins:L market:y bank:n"
+impl Display for StateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let yes_no = |b: bool| if b { "y" } else { "n" };
+        write!(
+            f,
+            "{:?} energy:{} backpack:{} coins:{} market:{} bank:{}",
+            self.outcome,
+            self.energy,
+            self.backpack,
+            self.coins,
+            yes_no(self.market_known),
+            yes_no(self.bank_known)
+        )
+    }
+}
+
+// Continuous description of what the robot observes, each value between 0 and 1, used by the linear approximation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Features {
+    pub energy: f64,
+    pub backpack_fill: f64,
+    // Coins held, relative to the size of the backpack
+    pub coins: f64,
+    // Distances relative to the largest one in the map, 1 when nothing has been discovered yet
+    pub bank_distance: f64,
+    pub market_distance: f64,
+}
+
+impl Features {
+    pub fn new(observation: &Observation) -> Self {
+        let ratio = |n: usize, max: usize| (n as f64 / max.max(1) as f64).min(1.0);
+        let distance = |d: Option<usize>| d.map_or(1.0, |d| ratio(d, 2 * observation.map_size));
+
+        Features {
+            energy: ratio(observation.energy, MAX_ENERGY),
+            backpack_fill: ratio(observation.backpack_used, observation.backpack_size),
+            coins: ratio(observation.coins, observation.backpack_size),
+            bank_distance: distance(observation.bank_distance),
+            market_distance: distance(observation.market_distance),
+        }
+    }
+
+    // Middle of the buckets of the key, for when only the key is known. Known banks and markets are put halfway
+    fn of_key(key: StateKey) -> Self {
+        let level = |l: Level| match l {
+            Level::Low => LOW_THRESHOLD / 2.0,
+            Level::Medium => (LOW_THRESHOLD + MEDIUM_THRESHOLD) / 2.0,
+            Level::High => (MEDIUM_THRESHOLD + 1.0) / 2.0,
+        };
+        let distance = |known: bool| if known { 0.5 } else { 1.0 };
+
+        Features {
+            energy: level(key.energy),
+            backpack_fill: level(key.backpack),
+            coins: match key.coins {
+                Level::Low => 0.0,
+                _ => level(key.coins),
+            },
+            bank_distance: distance(key.bank_known),
+            market_distance: distance(key.market_known),
+        }
+    }
+}
+
+// A state as the learners see it: the Q-table looks up the key, the linear approximation uses the features
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct State {
+    pub key: StateKey,
+    pub features: Features,
+}
+
+impl State {
+    pub fn new(outcome: States, observation: &Observation) -> Self {
+        State {
+            key: StateKey::encode(outcome, observation),
+            features: Features::new(observation),
+        }
+    }
+
+    // A typical state of the key, used to read a learner as a Q-table
+    pub fn of_key(key: StateKey) -> Self {
+        State {
+            key,
+            features: Features::of_key(key),
+        }
+    }
+}
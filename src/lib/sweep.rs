@@ -0,0 +1,346 @@
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+    thread,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::ai::{run_episode, EpisodeStatus};
+use super::config::{Exploration, Mode, TrainingConfig, ValueFunction};
+use super::learner::new_learner;
+use super::reward::TableRewards;
+use super::training::TrainerConfig;
+use super::utils::{blank_q_table, write_q_table_to, QTable};
+
+// The configurations tried by a sweep
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Search {
+    // Every combination of the listed values
+    Grid {
+        learning_rate: Vec<f64>,
+        discount: Vec<f64>,
+        epsilon: Vec<f64>,
+        rewards: Vec<TableRewards>,
+    },
+    // samples configurations drawn uniformly between the bounds, each reward between the values at the same index
+    Random {
+        samples: usize,
+        learning_rate: (f64, f64),
+        discount: (f64, f64),
+        epsilon: (f64, f64),
+        rewards: (TableRewards, TableRewards),
+    },
+}
+
+// A sweep: every configuration of search is trained from a blank Q-table on the worlds of trainer.seed,
+// then evaluated with the greedy policy on one world for each of eval_seeds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SweepConfig {
+    pub search: Search,
+    // World size, generator, goal and limits shared by all the configurations
+    pub trainer: TrainerConfig,
+    pub train_episodes: usize,
+    pub eval_seeds: Vec<u64>,
+    // Configurations trained at the same time, each on its own thread
+    pub workers: usize,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        SweepConfig {
+            search: Search::Grid {
+                learning_rate: vec![0.1],
+                discount: vec![0.9],
+                epsilon: vec![0.2],
+                rewards: vec![TableRewards::default()],
+            },
+            trainer: TrainerConfig::default(),
+            train_episodes: 10,
+            eval_seeds: vec![1, 2, 3],
+            workers: 1,
+        }
+    }
+}
+
+impl SweepConfig {
+    // Loads the sweep from a RON file, missing fields take the default value
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(|e| format!("File read: {e}"))?;
+
+        ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))
+    }
+}
+
+// The values tried in a configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub learning_rate: f64,
+    pub discount: f64,
+    pub epsilon: f64,
+    pub rewards: TableRewards,
+}
+
+// How a configuration did on the evaluation worlds
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+    pub candidate: Candidate,
+    pub goals_reached: usize,
+    pub evaluations: usize,
+    pub mean_ticks: f64,
+    pub mean_coins: f64,
+    pub q_table: QTable,
+}
+
+impl SweepResult {
+    // Best first: more goals reached, then fewer ticks, then more coins deposited
+    fn rank(&self, other: &SweepResult) -> Ordering {
+        other
+            .goals_reached
+            .cmp(&self.goals_reached)
+            .then(self.mean_ticks.total_cmp(&other.mean_ticks))
+            .then(other.mean_coins.total_cmp(&self.mean_coins))
+    }
+}
+
+// Lists the configurations of the search, random ones are drawn from the seed of the sweep
+pub fn candidates(config: &SweepConfig) -> Vec<Candidate> {
+    match &config.search {
+        Search::Grid {
+            learning_rate,
+            discount,
+            epsilon,
+            rewards,
+        } => {
+            let mut candidates = Vec::new();
+            for &learning_rate in learning_rate {
+                for &discount in discount {
+                    for &epsilon in epsilon {
+                        for rewards in rewards {
+                            candidates.push(Candidate {
+                                learning_rate,
+                                discount,
+                                epsilon,
+                                rewards: *rewards,
+                            });
+                        }
+                    }
+                }
+            }
+            candidates
+        }
+        Search::Random {
+            samples,
+            learning_rate,
+            discount,
+            epsilon,
+            rewards,
+        } => {
+            let mut rng = StdRng::seed_from_u64(config.trainer.seed);
+            let mut between = |(low, high): (f64, f64)| low + (high - low) * rng.gen::<f64>();
+            (0..*samples)
+                .map(|_| Candidate {
+                    learning_rate: between(*learning_rate),
+                    discount: between(*discount),
+                    epsilon: between(*epsilon),
+                    rewards: TableRewards(std::array::from_fn(|i| {
+                        between((rewards.0 .0[i], rewards.1 .0[i]))
+                    })),
+                })
+                .collect()
+        }
+    }
+}
+
+// Trains and evaluates every configuration, returns them ranked from the best
+pub fn sweep(config: &SweepConfig) -> Result<Vec<SweepResult>, String> {
+    if config.trainer.training.value_function != ValueFunction::Table {
+        return Err("Sweeps are only available with the Q-table".to_string());
+    }
+    if config.workers == 0 {
+        return Err("A sweep needs at least one worker".to_string());
+    }
+    config.trainer.validate_worlds()?;
+    let candidates = candidates(config)
+        .into_iter()
+        .map(|candidate| {
+            let training = candidate_training(config, &candidate);
+            training
+                .validate()
+                .map_err(|e| format!("Invalid configuration {candidate:?}: {e}"))?;
+            Ok((candidate, training))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // A world the configurations were trained on would reward remembering it rather than generalizing
+    let (policy_seed, train_seeds) = train_seeds(config);
+    if let Some(seed) = config.eval_seeds.iter().find(|s| train_seeds.contains(s)) {
+        return Err(format!(
+            "The evaluation seed {seed} is also one of the training worlds"
+        ));
+    }
+
+    // Configuration i is played by worker i % workers
+    let total = candidates.len();
+    let mut results = thread::scope(|scope| {
+        let handles: Vec<_> = (0..config.workers)
+            .map(|k| {
+                let assigned: Vec<(usize, (Candidate, TrainingConfig))> = candidates
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .filter(|(i, _)| i % config.workers == k)
+                    .collect();
+                let train_seeds = &train_seeds;
+                scope.spawn(move || {
+                    assigned
+                        .into_iter()
+                        .map(|(i, (candidate, training))| {
+                            println!("Configuration {}/{total}: {candidate:?}", i + 1);
+                            try_candidate(config, candidate, &training, policy_seed, train_seeds)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().map_err(|_| "A sweep worker panicked".to_string()))
+            .collect::<Result<Vec<_>, String>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    results.sort_by(|r1, r2| r1.rank(r2));
+    Ok(results)
+}
+
+// The hyperparameters the candidate is trained with
+fn candidate_training(config: &SweepConfig, candidate: &Candidate) -> TrainingConfig {
+    TrainingConfig {
+        learning_rate: candidate.learning_rate,
+        discount: candidate.discount,
+        exploration: Exploration::Constant(candidate.epsilon),
+        ..config.trainer.training.clone()
+    }
+}
+
+// Seed of the policy and seeds of the training worlds, the same for every configuration. The worlds are drawn
+// apart from the policy, so that they don't depend on its choices
+fn train_seeds(config: &SweepConfig) -> (u64, Vec<u64>) {
+    let mut rng = StdRng::seed_from_u64(config.trainer.seed);
+    let policy_seed = rng.gen::<u64>();
+    let worlds = (0..config.train_episodes)
+        .map(|_| rng.gen::<u64>())
+        .collect();
+    (policy_seed, worlds)
+}
+
+// Trains a blank Q-table with the candidate, then plays one greedy episode on each evaluation world
+fn try_candidate(
+    config: &SweepConfig,
+    candidate: Candidate,
+    training: &TrainingConfig,
+    policy_seed: u64,
+    train_seeds: &[u64],
+) -> SweepResult {
+    let trainer = &config.trainer;
+
+    let mut rng = StdRng::seed_from_u64(policy_seed);
+    let mut learner = new_learner(training, blank_q_table(), rng.gen::<u64>());
+    let mut ticks_played = 0;
+    for seed in train_seeds {
+        let result = run_episode(
+            trainer.world_size,
+            &trainer.generator,
+            &candidate.rewards,
+            learner.as_mut(),
+            training,
+            ticks_played,
+            *seed,
+            &mut rng,
+            Mode::Training,
+        );
+        ticks_played += result.summary.ticks;
+    }
+
+    let mut goals_reached = 0;
+    let mut ticks = 0;
+    let mut coins = 0;
+    for seed in &config.eval_seeds {
+        let summary = run_episode(
+            trainer.world_size,
+            &trainer.generator,
+            &candidate.rewards,
+            learner.as_mut(),
+            training,
+            0,
+            *seed,
+            &mut StdRng::seed_from_u64(*seed),
+            Mode::Evaluation,
+        )
+        .summary;
+        if summary.status == EpisodeStatus::GoalReached {
+            goals_reached += 1;
+        }
+        ticks += summary.ticks;
+        coins += summary.coins_deposited;
+    }
+
+    let evaluations = config.eval_seeds.len().max(1) as f64;
+    SweepResult {
+        candidate,
+        goals_reached,
+        evaluations: config.eval_seeds.len(),
+        mean_ticks: ticks as f64 / evaluations,
+        mean_coins: coins as f64 / evaluations,
+        q_table: learner.q_table(),
+    }
+}
+
+// The ranked results as a Markdown table
+pub fn report(results: &[SweepResult]) -> String {
+    let mut output = String::from(
+        "| Rank | α | γ | ε | Rewards | Goals reached | Mean ticks | Mean coins |\n|---:|---:|---:|---:|---|---:|---:|---:|\n",
+    );
+    for (i, result) in results.iter().enumerate() {
+        let c = &result.candidate;
+        let rewards: Vec<String> = c.rewards.0.iter().map(|r| format!("{r:.2}")).collect();
+        output += &format!(
+            "| {} | {:.3} | {:.3} | {:.3} | [{}] | {}/{} | {:.1} | {:.1} |\n",
+            i + 1,
+            c.learning_rate,
+            c.discount,
+            c.epsilon,
+            rewards.join(", "),
+            result.goals_reached,
+            result.evaluations,
+            result.mean_ticks,
+            result.mean_coins
+        );
+    }
+    output
+}
+
+// Writes the rewards and the Q-table of a configuration in the given directory, as rewards.ron and
+// custom_q_table.txt: the files the custom robot is trained and run with
+pub fn write_best(result: &SweepResult, directory: &str) -> Result<(), String> {
+    fs::create_dir_all(directory).map_err(|e| format!("Directory create: {e}"))?;
+
+    let rewards =
+        ron::to_string(&result.candidate.rewards).map_err(|e| format!("Serializing: {e}"))?;
+    fs::write(Path::new(directory).join("rewards.ron"), rewards)
+        .map_err(|e| format!("File write: {e}"))?;
+
+    let path = Path::new(directory).join("custom_q_table.txt");
+    write_q_table_to(&path.to_string_lossy(), &result.q_table)
+}
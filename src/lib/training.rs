@@ -0,0 +1,360 @@
+use std::{fmt::Display, fs::File, io::Read};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::ai::{run_episode, EpisodeSummary, TickRecord};
+use super::checkpoint::Checkpoint;
+use super::config::{Mode, TrainingConfig, WorldConfig};
+use super::convergence::ConvergenceTracker;
+use super::curriculum::CurriculumStage;
+use super::learner::{learner_path, load_learner_from, Learner};
+use super::metrics::{MetricsConfig, MetricsWriter};
+use super::parallel::{train_parallel, ParallelConfig};
+use super::reward::TableRewards;
+
+// Everything the trainer needs to run a training session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrainerConfig {
+    pub world_size: usize,
+    pub generator: WorldConfig,
+    pub rewards: TableRewards,
+    // Whether to train the default table (q_table.txt) or the custom one (custom_q_table.txt)
+    pub default_rewards: bool,
+    // File the learner is read from and file it is written to, instead of the default or custom one.
+    // Without output the learner is written back to input
+    pub input: Option<String>,
+    pub output: Option<String>,
+    // Starts from a robot that has never been trained instead of reading input
+    pub blank: bool,
+    // The Q-table is written every checkpoint_every episodes, and always at the end
+    pub checkpoint_every: Option<usize>,
+    // File where the state of the session is written together with the Q-table, None to skip it
+    pub checkpoint: Option<String>,
+    // Continues the session of the checkpoint instead of starting a new one, up to the same number of episodes
+    pub resume: bool,
+    // Seed of the whole session, every episode gets a different world derived from it
+    pub seed: u64,
+    pub training: TrainingConfig,
+    // CSV files where the metrics of every episode are written, None to skip them
+    pub metrics: Option<MetricsConfig>,
+    // Trains on several worlds at the same time, None to use a single thread
+    pub parallel: Option<ParallelConfig>,
+    // Stages played by train_curriculum(), in order
+    pub curriculum: Vec<CurriculumStage>,
+}
+
+impl Default for TrainerConfig {
+    fn default() -> Self {
+        TrainerConfig {
+            world_size: 100,
+            generator: WorldConfig::default(),
+            rewards: TableRewards::default(),
+            default_rewards: false,
+            input: None,
+            output: None,
+            blank: false,
+            checkpoint_every: None,
+            checkpoint: None,
+            resume: false,
+            seed: 0,
+            training: TrainingConfig::default(),
+            metrics: None,
+            parallel: None,
+            curriculum: Vec::new(),
+        }
+    }
+}
+
+impl TrainerConfig {
+    // Loads the trainer configuration from a RON file, missing fields take the default value
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("File open: {e}"))?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer)
+            .map_err(|e| format!("File read: {e}"))?;
+
+        let config: TrainerConfig =
+            ron::from_str(&buffer).map_err(|e| format!("Deserializing: {e}"))?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // Checks the hyperparameters and the world settings, a config built in code is not checked by load
+    pub fn validate(&self) -> Result<(), String> {
+        self.training.validate()?;
+        self.validate_worlds()
+    }
+
+    // Checks the world settings of the session and of every stage of its curriculum, which may not come from a file
+    pub fn validate_worlds(&self) -> Result<(), String> {
+        self.generator.validate()?;
+        for (i, stage) in self.curriculum.iter().enumerate() {
+            stage
+                .generator
+                .validate()
+                .map_err(|e| format!("Stage {}: {e}", i + 1))?;
+        }
+        Ok(())
+    }
+
+    // File the learner is read from
+    pub fn input_path(&self) -> String {
+        self.input
+            .clone()
+            .unwrap_or_else(|| learner_path(&self.training, self.default_rewards))
+    }
+
+    // File the learner is written to
+    pub fn output_path(&self) -> String {
+        self.output.clone().unwrap_or_else(|| self.input_path())
+    }
+
+    // The learner the session starts from
+    pub(crate) fn initial_learner(&self, seed: u64) -> Result<Box<dyn Learner>, String> {
+        let input = self.input_path();
+        let path = if self.blank {
+            None
+        } else {
+            Some(input.as_str())
+        };
+        load_learner_from(&self.training, path, seed)
+    }
+
+    // The checkpoint to continue from when resuming, None for a new session. It must have been written by a session
+    // with the same settings. The replay buffer is not checkpointed, parallel workers start a new one every round anyway
+    pub(crate) fn resumed(&self, episodes: usize) -> Result<Option<Checkpoint>, String> {
+        if !self.resume {
+            return Ok(None);
+        }
+        if self.training.replay.is_some() && self.parallel.is_none() {
+            return Err(
+                "A session with a replay buffer cannot be resumed, the buffer is not checkpointed"
+                    .to_string(),
+            );
+        }
+        let path = self
+            .checkpoint
+            .as_deref()
+            .ok_or("Resuming needs a checkpoint file")?;
+        let checkpoint = Checkpoint::load(path).map_err(|e| format!("Checkpoint loading: {e}"))?;
+        checkpoint.check(self, episodes)?;
+        println!(
+            "Resuming after episode {} from {path}",
+            checkpoint.summaries.len()
+        );
+        Ok(Some(checkpoint))
+    }
+}
+
+// Why a training session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    // Every episode, or every stage of the curriculum, was played
+    Completed,
+    // The Q-table stopped changing, at the given episode numbered from 1
+    Converged { episode: usize },
+    // A stage of the curriculum, numbered from 1, missed its success rate in every attempt
+    StageFailed { stage: usize },
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Completed => write!(f, "every episode was played"),
+            StopReason::Converged { episode } => {
+                write!(f, "the Q-table converged at episode {episode}")
+            }
+            StopReason::StageFailed { stage } => {
+                write!(f, "stage {stage} of the curriculum missed its success rate")
+            }
+        }
+    }
+}
+
+// The episodes played by a training session and why it ended
+#[derive(Debug, Clone)]
+pub struct TrainingReport {
+    pub summaries: Vec<EpisodeSummary>,
+    pub stop: StopReason,
+}
+
+// Runs the given number of episodes in the same process, each on a new world, carrying the Q-table between them.
+// With a convergence config it stops as soon as the Q-table has stopped changing
+pub fn train(episodes: usize, config: &TrainerConfig) -> Result<TrainingReport, String> {
+    config.validate()?;
+    if let Some(parallel) = &config.parallel {
+        return train_parallel(episodes, config, parallel);
+    }
+
+    let (mut learner, mut session) = match config.resumed(episodes)? {
+        // The random generator of the learner is reseeded before every episode, the seed does not matter
+        Some(session) => (
+            load_learner_from(&config.training, Some(&session.learner), session.rng_seed)?,
+            session,
+        ),
+        None => {
+            let mut rng = StdRng::seed_from_u64(config.seed);
+            let learner = config.initial_learner(rng.gen::<u64>())?;
+            let session = Checkpoint::new(config, episodes, rng.gen::<u64>());
+            (learner, session)
+        }
+    };
+    let path = config.output_path();
+    let mut rng = StdRng::seed_from_u64(session.rng_seed);
+    let mut metrics = create_metrics(config, &session.summaries)?;
+    let mut convergence =
+        ConvergenceTracker::resume(config.training.convergence.clone(), session.stable_episodes);
+    let mut stop = StopReason::Completed;
+
+    while session.summaries.len() < episodes {
+        let episode = session.summaries.len() + 1;
+        // The session RNG is the whole state of the learner's choices too, so a resumed session makes the same ones
+        learner.reseed(rng.gen::<u64>());
+        let result = run_episode(
+            config.world_size,
+            &config.generator,
+            &config.rewards,
+            learner.as_mut(),
+            &config.training,
+            session.ticks_played,
+            rng.gen::<u64>(),
+            &mut rng,
+            Mode::Training,
+        );
+        report_episode(
+            episode,
+            Some(episodes),
+            &result.summary,
+            &result.tick_log,
+            &mut metrics,
+        )?;
+        let converged = convergence.observe(result.summary.q_change);
+
+        session.ticks_played += result.summary.ticks;
+        session.stable_episodes = convergence.stable_episodes();
+        session.summaries.push(result.summary);
+        session.rng_seed = rng.gen::<u64>();
+        rng = StdRng::seed_from_u64(session.rng_seed);
+
+        if converged {
+            stop = StopReason::Converged { episode };
+            break;
+        }
+
+        // Intermediate checkpoint, the last episode is written below anyway
+        if let Some(every) = config.checkpoint_every {
+            if every > 0 && episode % every == 0 && episode < episodes {
+                learner
+                    .save(&path)
+                    .map_err(|e| format!("Q-Table writing: {e}"))?;
+                write_checkpoint(config, &mut session, |file| learner.save(file))?;
+            }
+        }
+    }
+
+    learner
+        .save(&path)
+        .map_err(|e| format!("Q-Table writing: {e}"))?;
+    write_checkpoint(config, &mut session, |file| learner.save(file))?;
+
+    Ok(TrainingReport {
+        summaries: session.summaries,
+        stop,
+    })
+}
+
+// Writes the checkpoint of the session, if the config has one, after writing its learner with save
+pub(crate) fn write_checkpoint(
+    config: &TrainerConfig,
+    session: &mut Checkpoint,
+    save: impl FnOnce(&str) -> Result<(), String>,
+) -> Result<(), String> {
+    let Some(path) = &config.checkpoint else {
+        return Ok(());
+    };
+    let learner = session.next_learner_path(path);
+    save(&learner).map_err(|e| format!("Q-Table writing: {e}"))?;
+    session.learner = learner;
+    session
+        .write(path)
+        .map_err(|e| format!("Checkpoint writing: {e}"))
+}
+
+// Opens the metrics files of the config, if any, with the rows of the episodes already played when resuming
+pub(crate) fn create_metrics(
+    config: &TrainerConfig,
+    played: &[EpisodeSummary],
+) -> Result<Option<MetricsWriter>, String> {
+    let writer = match &config.metrics {
+        Some(metrics) if played.is_empty() => MetricsWriter::create(metrics),
+        Some(metrics) => MetricsWriter::resume(metrics, played),
+        None => return Ok(None),
+    };
+    writer.map(Some).map_err(|e| format!("Metrics: {e}"))
+}
+
+// Prints the outcome of an episode, numbered from 1 out of episodes if the total is known, and writes its metrics
+pub(crate) fn report_episode(
+    episode: usize,
+    episodes: Option<usize>,
+    summary: &EpisodeSummary,
+    tick_log: &[TickRecord],
+    metrics: &mut Option<MetricsWriter>,
+) -> Result<(), String> {
+    if let Some(metrics) = metrics {
+        metrics
+            .log_episode(episode, summary, tick_log)
+            .map_err(|e| format!("Metrics: {e}"))?;
+    }
+
+    let progress = match episodes {
+        Some(episodes) => format!("{episode}/{episodes}"),
+        None => episode.to_string(),
+    };
+    let change = match summary.q_change {
+        Some(change) => format!(
+            ", max Q change {:.4}, {} greedy actions changed",
+            change.max_delta, change.policy_changes
+        ),
+        None => String::new(),
+    };
+    println!(
+        "Episode {}: {:?} after {} ticks, {} coins deposited, total reward {:.2}{}",
+        progress,
+        summary.status,
+        summary.ticks,
+        summary.coins_deposited,
+        summary.total_reward,
+        change
+    );
+    Ok(())
+}
+
+// Plays the given number of episodes with the greedy policy, without updating nor writing the Q-table
+pub fn evaluate(episodes: usize, config: &TrainerConfig) -> Result<Vec<EpisodeSummary>, String> {
+    config.validate()?;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut learner = config.initial_learner(rng.gen::<u64>())?;
+    let mut summaries = Vec::with_capacity(episodes);
+
+    for _ in 0..episodes {
+        let summary = run_episode(
+            config.world_size,
+            &config.generator,
+            &config.rewards,
+            learner.as_mut(),
+            &config.training,
+            0,
+            rng.gen::<u64>(),
+            &mut rng,
+            Mode::Evaluation,
+        )
+        .summary;
+        summaries.push(summary);
+    }
+
+    Ok(summaries)
+}
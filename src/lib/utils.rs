@@ -10,22 +10,28 @@ use robotics_lib::{
     },
 };
 
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
     collections::{HashMap, VecDeque},
-    fs::File,
-    io::{BufRead, BufReader, Write},
+    fmt::Debug,
+    fs::{self, File},
+    hash::Hash,
+    io::Write,
     rc::Rc,
 };
 
 use crate::{actions::ActionErr, my_events::MyEvents2};
 
-use super::data_storage::{self, push_event, MyEvent};
+use super::data_storage::{MyEvent, Recording};
+use super::goal::Collectable;
+use super::state::{Observation, StateKey, MAX_ENERGY};
 
 use super::actions::{self};
 
 // All the possible actions the robot can make
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Action {
     Recycle,
     Sell,
@@ -37,10 +43,96 @@ pub enum Action {
     DepositInBank,
     ExploreNearings,
     ExploreUnknown,
+    // Waits a tick, letting the energy recharge
+    Rest,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Recycle,
+        Action::Sell,
+        Action::DestroyTree,
+        Action::DestroyRock,
+        Action::DestroyGarbage,
+        Action::DestroyCoin,
+        Action::DestroyFish,
+        Action::DepositInBank,
+        Action::ExploreNearings,
+        Action::ExploreUnknown,
+        Action::Rest,
+    ];
+
+    // Position of the action in ALL
+    pub fn index(&self) -> usize {
+        Action::ALL.iter().position(|a| a == self).unwrap()
+    }
+
+    // Whether the action can do anything given the backpack, the energy and the contents the robot knows about.
+    // Resting is pointless with full energy, and nothing but resting can be done without energy
+    pub fn is_valid(&self, observation: &Observation) -> bool {
+        if *self == Action::Rest {
+            return observation.energy < MAX_ENERGY;
+        }
+        if observation.energy == 0 {
+            return false;
+        }
+
+        let amount = |content: Content| *observation.backpack_contents.get(&content).unwrap_or(&0);
+        let sellable: usize = actions::SELLABLE.into_iter().map(amount).sum();
+        let full = observation.backpack_used >= observation.backpack_size;
+        let can_destroy = |item: Collectable| !full && observation.known_contents.contains(&item);
+
+        match self {
+            // A coin is crafted out of 5 garbage
+            Action::Recycle => amount(Content::Garbage(0)) >= 5,
+            Action::Sell => sellable > 0 && observation.market_known,
+            Action::DestroyTree => can_destroy(Collectable::Tree),
+            Action::DestroyRock => can_destroy(Collectable::Rock),
+            Action::DestroyGarbage => can_destroy(Collectable::Garbage),
+            Action::DestroyCoin => can_destroy(Collectable::Coin),
+            Action::DestroyFish => can_destroy(Collectable::Fish),
+            Action::DepositInBank => observation.coins > 0 && observation.bank_known,
+            Action::ExploreNearings | Action::ExploreUnknown | Action::Rest => true,
+        }
+    }
+}
+
+// The actions the robot is allowed to choose in a tick, in the order of Action::ALL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionMask([bool; Action::ALL.len()]);
+
+impl ActionMask {
+    // Every action allowed, when nothing is known about the robot
+    pub fn all() -> Self {
+        ActionMask([true; Action::ALL.len()])
+    }
+
+    // The actions that are valid for the observation, or all of them if none is
+    pub fn new(observation: &Observation) -> Self {
+        let mut allowed = [false; Action::ALL.len()];
+        for action in Action::ALL {
+            allowed[action.index()] = action.is_valid(observation);
+        }
+        if !allowed.contains(&true) {
+            return ActionMask::all();
+        }
+        ActionMask(allowed)
+    }
+
+    pub fn allows(&self, action: Action) -> bool {
+        self.0[action.index()]
+    }
+
+    pub fn actions(&self) -> Vec<Action> {
+        Action::ALL
+            .into_iter()
+            .filter(|a| self.allows(*a))
+            .collect()
+    }
 }
 
 // All the possible states the robot can be in
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum States {
     Start,
     Goal,
@@ -52,36 +144,13 @@ pub enum States {
     BackpackFullCoins,
     BackpackFullItems,
     Neutral,
+    // The robot waited for its energy to recharge
+    Rested,
 }
 
-// Functions that loads the q_table from file
-pub(crate) fn load_q_table(
-    default_rewards: bool,
-) -> Result<HashMap<(States, Action), f64>, String> {
-    let current_dir = std::env::current_dir().unwrap();
-
-    let mut path = String::new();
-    if default_rewards {
-        path += "/q_table.txt";
-    } else {
-        path += "/custom_q_table.txt";
-    }
-
-    let file_path = current_dir.display().to_string() + path.as_str();
-
-    let input = File::open(file_path);
-
-    match input.as_ref() {
-        Ok(_) => {}
-        Err(e) => return Err(format!("File open: {e}")),
-    }
-
-    let buffered = BufReader::new(input.unwrap());
-
-    let mut q_table: HashMap<(States, Action), f64> = HashMap::new();
-
-    // States and Actions
-    let states = vec![
+impl States {
+    // The states stored in the Q-table, Sold and PutInBank are always stored with 0 items
+    pub const ALL: [States; 11] = [
         States::Start,
         States::Goal,
         States::Destroyed,
@@ -92,59 +161,293 @@ pub(crate) fn load_q_table(
         States::BackpackFullCoins,
         States::BackpackFullItems,
         States::Neutral,
+        States::Rested,
     ];
+}
 
-    let actions = vec![
-        Action::Recycle,
-        Action::Sell,
-        Action::DestroyTree,
-        Action::DestroyRock,
-        Action::DestroyGarbage,
-        Action::DestroyCoin,
-        Action::DestroyFish,
-        Action::DepositInBank,
-        Action::ExploreNearings,
-        Action::ExploreUnknown,
-    ];
+pub type QTable = HashMap<(StateKey, Action), f64>;
+
+// Version of the Q-table file format, increased every time the format or the states and actions change.
+// Version 1 was keyed by States only, version 2 by the full StateKey, version 3 added Rest and Rested,
+// version 4 can hold the second table of Double Q-learning
+pub const Q_TABLE_VERSION: u32 = 4;
+
+// The actions of the tables before version 3, Rest was added at the end of ALL
+const OLD_ACTIONS: [Action; 10] = [
+    Action::Recycle,
+    Action::Sell,
+    Action::DestroyTree,
+    Action::DestroyRock,
+    Action::DestroyGarbage,
+    Action::DestroyCoin,
+    Action::DestroyFish,
+    Action::DepositInBank,
+    Action::ExploreNearings,
+    Action::ExploreUnknown,
+];
+
+// Q-table as it is written on file: every value is labelled with its state and action.
+// Double Q-learning writes its two tables in entries and second
+#[derive(Serialize, Deserialize)]
+struct QTableFile {
+    version: u32,
+    entries: Vec<QTableEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    second: Option<Vec<QTableEntry>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QTableEntry {
+    state: StateKey,
+    action: Action,
+    value: f64,
+}
+
+// Version 1 of the file, still readable to migrate old tables
+#[derive(Deserialize)]
+struct QTableFileV1 {
+    entries: Vec<QTableEntryV1>,
+}
+
+#[derive(Deserialize)]
+struct QTableEntryV1 {
+    state: States,
+    action: Action,
+    value: f64,
+}
+
+// Only the version, to know how to read the rest of the file
+#[derive(Deserialize)]
+struct QTableHeader {
+    version: u32,
+}
+
+// Returns the action with the highest Q-value in the given state
+pub fn greedy_action(q_table: &QTable, state: StateKey) -> Action {
+    Action::ALL
+        .iter()
+        .max_by(|&&a1, &&a2| {
+            q_table[&(state, a1)]
+                .partial_cmp(&q_table[&(state, a2)])
+                .unwrap()
+        })
+        .unwrap()
+        .clone()
+}
 
-    let mut q_values = Vec::new();
-    for line in buffered.lines() {
-        match line {
-            Ok(val) => q_values.push(val),
-            Err(_) => {}
+// Q-table of a robot that has never been trained, every value is 0
+pub fn blank_q_table() -> QTable {
+    let mut q_table = QTable::new();
+    for state in StateKey::all() {
+        for action in Action::ALL {
+            q_table.insert((state, action), 0.0);
+        }
+    }
+    q_table
+}
+
+// Path of the Q-table of the default or of the custom robot
+pub fn q_table_path(default_rewards: bool) -> String {
+    if default_rewards {
+        return "q_table.txt".to_string();
+    }
+    "custom_q_table.txt".to_string()
+}
+
+// Functions that loads the q_table from file
+pub fn load_q_table(default_rewards: bool) -> Result<QTable, String> {
+    load_q_table_from(&q_table_path(default_rewards))
+}
+
+// Loads a Q-table from the given path. A file holding the two tables of Double Q-learning gives their average,
+// which is what the robot acts on
+pub fn load_q_table_from(path: &str) -> Result<QTable, String> {
+    let (first, second) = load_q_tables_from(path)?;
+    match second {
+        Some(second) => Ok(first
+            .into_iter()
+            .map(|(key, value)| (key, (value + second[&key]) / 2.0))
+            .collect()),
+        None => Ok(first),
+    }
+}
+
+// Loads the two tables of Double Q-learning from the given path. A file with a single table, e.g. written
+// by another algorithm, starts both from it
+pub fn load_q_table_pair_from(path: &str) -> Result<(QTable, QTable), String> {
+    let (first, second) = load_q_tables_from(path)?;
+    let second = second.unwrap_or_else(|| first.clone());
+    Ok((first, second))
+}
+
+// Loads the table of the given path, and its second table if it has one, rejecting unknown versions, missing or extra entries.
+// Version 1 tables are migrated: each value is copied to every StateKey with the same outcome.
+// Older tables don't know Rest and Rested, see add_rest
+fn load_q_tables_from(path: &str) -> Result<(QTable, Option<QTable>), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("File open: {e}"))?;
+
+    let header: QTableHeader = ron::from_str(&content)
+        .map_err(|e| format!("{path} is not a valid Q-table, it must be trained again: {e}"))?;
+
+    match header.version {
+        1 => {
+            let file: QTableFileV1 =
+                ron::from_str(&content).map_err(|e| format!("Deserializing {path}: {e}"))?;
+            let entries = file
+                .entries
+                .into_iter()
+                .map(|e| (e.state, e.action, e.value));
+            let old_states = &States::ALL[..States::ALL.len() - 1];
+            let old_table = build_table(entries, old_states, &OLD_ACTIONS, path)?;
+
+            let mut q_table = QTable::new();
+            for state in StateKey::all() {
+                if state.outcome == States::Rested {
+                    continue;
+                }
+                for action in OLD_ACTIONS {
+                    q_table.insert((state, action), old_table[&(state.outcome, action)]);
+                }
+            }
+            Ok((add_rest(q_table), None))
+        }
+        2 => {
+            let file: QTableFile =
+                ron::from_str(&content).map_err(|e| format!("Deserializing {path}: {e}"))?;
+            let entries = file
+                .entries
+                .into_iter()
+                .map(|e| (e.state, e.action, e.value));
+            let old_states: Vec<StateKey> = StateKey::all()
+                .into_iter()
+                .filter(|s| s.outcome != States::Rested)
+                .collect();
+            let old_table = build_table(entries, &old_states, &OLD_ACTIONS, path)?;
+            Ok((add_rest(old_table), None))
+        }
+        3 | Q_TABLE_VERSION => {
+            let file: QTableFile =
+                ron::from_str(&content).map_err(|e| format!("Deserializing {path}: {e}"))?;
+            let table = |entries: Vec<QTableEntry>| {
+                let entries = entries.into_iter().map(|e| (e.state, e.action, e.value));
+                build_table(entries, &StateKey::all(), &Action::ALL, path)
+            };
+            let second = file.second.map(table).transpose()?;
+            Ok((table(file.entries)?, second))
+        }
+        v => Err(format!(
+            "{path} has version {v}, expected at most {Q_TABLE_VERSION}"
+        )),
+    }
+}
+
+// Completes a table written before Rest existed. Rested starts from the values of Neutral, the outcome the robot
+// got while waiting for energy, and Rest from the worst action of the state, so that it is never preferred untried
+fn add_rest(mut q_table: QTable) -> QTable {
+    for state in StateKey::all() {
+        if state.outcome == States::Rested {
+            let neutral = StateKey {
+                outcome: States::Neutral,
+                ..state
+            };
+            for action in OLD_ACTIONS {
+                q_table.insert((state, action), q_table[&(neutral, action)]);
+            }
+        }
+        let worst = OLD_ACTIONS
+            .iter()
+            .map(|a| q_table[&(state, *a)])
+            .fold(f64::MAX, f64::min);
+        q_table.insert((state, Action::Rest), worst);
+    }
+    q_table
+}
+
+// Builds a table from the entries of a file, every state must appear once with every action
+fn build_table<S: Copy + Eq + Hash + Debug>(
+    entries: impl Iterator<Item = (S, Action, f64)>,
+    states: &[S],
+    actions: &[Action],
+    path: &str,
+) -> Result<HashMap<(S, Action), f64>, String> {
+    let mut q_table = HashMap::new();
+    for (state, action, value) in entries {
+        if !states.contains(&state) {
+            return Err(format!("Unknown state {state:?} in {path}"));
+        }
+        if !actions.contains(&action) {
+            return Err(format!("Unknown action {action:?} in {path}"));
+        }
+        if !value.is_finite() {
+            return Err(format!(
+                "Invalid value {value} for ({state:?}, {action:?}) in {path}"
+            ));
+        }
+        if q_table.insert((state, action), value).is_some() {
+            return Err(format!(
+                "Duplicated entry ({state:?}, {action:?}) in {path}"
+            ));
         }
     }
 
-    let mut i = 0;
     for s in states {
-        let mut j = 0;
-        for a in actions.iter() {
-            q_table.insert(
-                (s, a.clone()),
-                q_values[i * actions.len() + j].parse::<f64>().unwrap(),
-            );
-            j += 1;
+        for a in actions {
+            if !q_table.contains_key(&(*s, *a)) {
+                return Err(format!("Missing entry ({s:?}, {a:?}) in {path}"));
+            }
         }
-        i += 1;
     }
 
-    return Ok(q_table);
+    Ok(q_table)
 }
 
 // Functions that writes the resulting q_table to file
-pub(crate) fn write_q_table(q_table: HashMap<(States, Action), f64>, default_rewards: bool) {
-    let mut path = String::new();
-    if default_rewards {
-        path += "q_table.txt";
-    } else {
-        path += "custom_q_table.txt";
-    }
+pub fn write_q_table(q_table: &QTable, default_rewards: bool) -> Result<(), String> {
+    write_q_table_to(&q_table_path(default_rewards), q_table)
+}
+
+// Writes the Q-table to the given path, always in the same order: states first, then actions
+pub fn write_q_table_to(path: &str, q_table: &QTable) -> Result<(), String> {
+    write_q_tables_to(path, q_table, None)
+}
 
-    let mut output = File::create(path.as_str()).unwrap();
+// Writes the two tables of Double Q-learning to the given path, in the same format
+pub fn write_q_table_pair_to(path: &str, first: &QTable, second: &QTable) -> Result<(), String> {
+    write_q_tables_to(path, first, Some(second))
+}
+
+fn write_q_tables_to(path: &str, first: &QTable, second: Option<&QTable>) -> Result<(), String> {
+    let file = QTableFile {
+        version: Q_TABLE_VERSION,
+        entries: table_entries(first)?,
+        second: second.map(table_entries).transpose()?,
+    };
+    let serialized = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .map_err(|e| format!("Serializing: {e}"))?;
+
+    let mut output = File::create(path).map_err(|e| format!("File create: {e}"))?;
+    output
+        .write_all(serialized.as_bytes())
+        .map_err(|e| format!("File write: {e}"))
+}
 
-    for val in q_table.iter() {
-        let _ = write!(output, "{}\n", val.1);
+// Entries of the Q-table as they are written on file
+fn table_entries(q_table: &QTable) -> Result<Vec<QTableEntry>, String> {
+    let states = StateKey::all();
+    let mut entries = Vec::with_capacity(states.len() * Action::ALL.len());
+    for state in states {
+        for action in Action::ALL {
+            let value = q_table
+                .get(&(state, action))
+                .ok_or(format!("Missing entry ({state}, {action:?})"))?;
+            entries.push(QTableEntry {
+                state,
+                action,
+                value: *value,
+            });
+        }
     }
+    Ok(entries)
 }
 
 // MyRobot struct
@@ -155,6 +458,12 @@ pub struct MyRobot {
     pub charted_map: Rc<RefCell<ChartedMap<Content>>>,
     pub past_events: Rc<RefCell<VecDeque<MyEvents2>>>,
     pub map: Rc<RefCell<Vec<Vec<Vec<Option<Tile>>>>>>,
+    pub rng: Rc<RefCell<StdRng>>,
+    pub energy_consumed: Rc<RefCell<usize>>,
+    pub observation: Rc<RefCell<Observation>>,
+    pub recording: Rc<RefCell<Recording>>,
+    // Energy at or below which the robot rests whatever the chosen action, None lets the policy decide
+    pub rest_threshold: Option<usize>,
 }
 
 // Implementation of the Runner trait for the MyRobot struct
@@ -165,13 +474,13 @@ impl Runnable for MyRobot {
 
         // If the robot is in the Start state, it just started, so it gives the spawn position to visualizer1
         if self.actual_state.as_ref().borrow().clone() == States::Start {
-            data_storage::clear_previous_data();
-
             let robot_spawn_position = (
                 self.get_coordinate().get_row(),
                 self.get_coordinate().get_col(),
             );
-            data_storage::save_initial_data(robot_spawn_position);
+            self.recording
+                .borrow_mut()
+                .save_initial_data(robot_spawn_position);
 
             self.past_events
                 .as_ref()
@@ -179,14 +488,27 @@ impl Runnable for MyRobot {
                 .push_back(MyEvents2::RobotSpawned(robot_spawn_position));
         }
 
-        // The robot only executes an action if it has more than 500 energy units left, otherwise it commonly doesn't manage to complete a task
-        if self.get_energy().get_energy_level() > 700 {
+        // The robot only executes an action if it has more than rest_threshold energy units left, otherwise it commonly doesn't manage to complete a task
+        let tired = self
+            .rest_threshold
+            .is_some_and(|threshold| self.get_energy().get_energy_level() <= threshold);
+        let action = self.actual_action.borrow().clone();
+
+        if tired || action == Action::Rest {
+            rest(self);
+        } else {
             let old_map = robot_map(world).unwrap();
             self.map.as_ref().borrow_mut().push(old_map.clone());
-            data_storage::update_initial_map(&robot_map(world).unwrap(), false);
+            self.recording
+                .borrow_mut()
+                .update_initial_map(&robot_map(world).unwrap(), false);
+
+            // Shared with the exploration functions, which also need the robot mutably
+            let rng = Rc::clone(&self.rng);
+            let recording = Rc::clone(&self.recording);
 
             // A match of all the possible actions. For each of them executes the snippet of code corresponding to that action
-            match self.actual_action.clone().borrow().clone() {
+            match action {
                 Action::Recycle => match actions::recycle(self) {
                     Ok(_) => {
                         self.actual_state.as_ref().replace(States::Recycled);
@@ -197,7 +519,9 @@ impl Runnable for MyRobot {
                 },
                 Action::Sell => match actions::sell(self, world, Rc::clone(&self.charted_map)) {
                     Ok(n) => {
-                        data_storage::update_initial_map(&robot_map(world).unwrap(), false);
+                        self.recording
+                            .borrow_mut()
+                            .update_initial_map(&robot_map(world).unwrap(), false);
                         self.past_events
                             .as_ref()
                             .borrow_mut()
@@ -228,10 +552,9 @@ impl Runnable for MyRobot {
                         ) {
                             Ok(value) => match value.0 {
                                 actions::ActionOk::Completed => {
-                                    data_storage::update_initial_map(
-                                        &robot_map(world).unwrap(),
-                                        false,
-                                    );
+                                    self.recording
+                                        .borrow_mut()
+                                        .update_initial_map(&robot_map(world).unwrap(), false);
                                     self.past_events.as_ref().borrow_mut().push_back(
                                         MyEvents2::ContentInteracted(Content::None, value.1),
                                     );
@@ -261,10 +584,9 @@ impl Runnable for MyRobot {
                         ) {
                             Ok(value) => match value.0 {
                                 actions::ActionOk::Completed => {
-                                    data_storage::update_initial_map(
-                                        &robot_map(world).unwrap(),
-                                        false,
-                                    );
+                                    self.recording
+                                        .borrow_mut()
+                                        .update_initial_map(&robot_map(world).unwrap(), false);
                                     self.past_events.as_ref().borrow_mut().push_back(
                                         MyEvents2::ContentInteracted(Content::None, value.1),
                                     );
@@ -294,10 +616,9 @@ impl Runnable for MyRobot {
                         ) {
                             Ok(value) => match value.0 {
                                 actions::ActionOk::Completed => {
-                                    data_storage::update_initial_map(
-                                        &robot_map(world).unwrap(),
-                                        false,
-                                    );
+                                    self.recording
+                                        .borrow_mut()
+                                        .update_initial_map(&robot_map(world).unwrap(), false);
                                     self.past_events.as_ref().borrow_mut().push_back(
                                         MyEvents2::ContentInteracted(Content::None, value.1),
                                     );
@@ -327,10 +648,9 @@ impl Runnable for MyRobot {
                         ) {
                             Ok(value) => match value.0 {
                                 actions::ActionOk::Completed => {
-                                    data_storage::update_initial_map(
-                                        &robot_map(world).unwrap(),
-                                        false,
-                                    );
+                                    self.recording
+                                        .borrow_mut()
+                                        .update_initial_map(&robot_map(world).unwrap(), false);
                                     self.past_events.as_ref().borrow_mut().push_back(
                                         MyEvents2::ContentInteracted(Content::None, value.1),
                                     );
@@ -360,10 +680,9 @@ impl Runnable for MyRobot {
                         ) {
                             Ok(value) => match value.0 {
                                 actions::ActionOk::Completed => {
-                                    data_storage::update_initial_map(
-                                        &robot_map(world).unwrap(),
-                                        false,
-                                    );
+                                    self.recording
+                                        .borrow_mut()
+                                        .update_initial_map(&robot_map(world).unwrap(), false);
                                     self.past_events.as_ref().borrow_mut().push_back(
                                         MyEvents2::ContentInteracted(Content::None, value.1),
                                     );
@@ -385,7 +704,9 @@ impl Runnable for MyRobot {
                     match actions::deposit_in_bank(self, world, Rc::clone(&self.charted_map)) {
                         Ok(n) => {
                             if n.0 > 0 {
-                                data_storage::update_initial_map(&robot_map(world).unwrap(), false);
+                                self.recording
+                                    .borrow_mut()
+                                    .update_initial_map(&robot_map(world).unwrap(), false);
                                 self.past_events
                                     .as_ref()
                                     .borrow_mut()
@@ -402,76 +723,87 @@ impl Runnable for MyRobot {
                         },
                     }
                 }
-                Action::ExploreNearings => match actions::explore_nearings(self, world, 5) {
-                    Ok(_) => {
-                        data_storage::update_initial_map(&robot_map(world).unwrap(), false); // Updates map of visualizer_1
-                        let mut backpack_size = self.get_backpack().get_size();
-                        let backpack_content = self.get_backpack().get_contents();
+                Action::ExploreNearings => {
+                    match actions::explore_nearings(self, world, 5, &recording) {
+                        Ok(_) => {
+                            self.recording
+                                .borrow_mut()
+                                .update_initial_map(&robot_map(world).unwrap(), false); // Updates map of visualizer_1
+                            let mut backpack_size = self.get_backpack().get_size();
+                            let backpack_content = self.get_backpack().get_contents();
 
-                        for item in backpack_content.iter() {
-                            backpack_size -= item.1.clone();
-                        }
+                            for item in backpack_content.iter() {
+                                backpack_size -= item.1.clone();
+                            }
 
-                        if backpack_size == 0 {
-                            self.actual_state.as_ref().replace(check_backpack(self));
-                        }
+                            if backpack_size == 0 {
+                                self.actual_state.as_ref().replace(check_backpack(self));
+                            }
 
-                        let new_map = robot_map(world).unwrap();
-                        self.past_events
-                            .as_ref()
-                            .borrow_mut()
-                            .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
-                        self.actual_state.as_ref().replace(States::Neutral);
-                    }
-                    Err(_) => {
-                        let new_map = robot_map(world).unwrap();
-                        self.past_events
-                            .as_ref()
-                            .borrow_mut()
-                            .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
-                        self.actual_state.as_ref().replace(States::NeedsExploring);
+                            let new_map = robot_map(world).unwrap();
+                            self.past_events
+                                .as_ref()
+                                .borrow_mut()
+                                .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
+                            self.actual_state.as_ref().replace(States::Neutral);
+                        }
+                        Err(_) => {
+                            let new_map = robot_map(world).unwrap();
+                            self.past_events
+                                .as_ref()
+                                .borrow_mut()
+                                .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
+                            self.actual_state.as_ref().replace(States::NeedsExploring);
+                        }
                     }
-                },
-                Action::ExploreUnknown => match actions::explore_unknown(self, world) {
-                    Ok(_) => {
-                        data_storage::update_initial_map(&robot_map(world).unwrap(), false); // Updates map of visualizer_1
-                        let mut backpack_size = self.get_backpack().get_size();
-                        let backpack_content = self.get_backpack().get_contents();
+                }
+                Action::ExploreUnknown => {
+                    match actions::explore_unknown(self, world, &mut rng.borrow_mut(), &recording) {
+                        Ok(_) => {
+                            self.recording
+                                .borrow_mut()
+                                .update_initial_map(&robot_map(world).unwrap(), false); // Updates map of visualizer_1
+                            let mut backpack_size = self.get_backpack().get_size();
+                            let backpack_content = self.get_backpack().get_contents();
 
-                        for item in backpack_content.iter() {
-                            backpack_size -= item.1.clone();
-                        }
+                            for item in backpack_content.iter() {
+                                backpack_size -= item.1.clone();
+                            }
 
-                        if backpack_size == 0 {
-                            self.actual_state.as_ref().replace(check_backpack(self));
-                        }
+                            if backpack_size == 0 {
+                                self.actual_state.as_ref().replace(check_backpack(self));
+                            }
 
-                        let new_map = robot_map(world).unwrap();
-                        self.past_events
-                            .as_ref()
-                            .borrow_mut()
-                            .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
-                        self.actual_state.as_ref().replace(States::Neutral);
-                    }
-                    Err(_) => {
-                        let new_map = robot_map(world).unwrap();
-                        self.past_events
-                            .as_ref()
-                            .borrow_mut()
-                            .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
-                        self.actual_state.as_ref().replace(States::NeedsExploring);
+                            let new_map = robot_map(world).unwrap();
+                            self.past_events
+                                .as_ref()
+                                .borrow_mut()
+                                .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
+                            self.actual_state.as_ref().replace(States::Neutral);
+                        }
+                        Err(_) => {
+                            let new_map = robot_map(world).unwrap();
+                            self.past_events
+                                .as_ref()
+                                .borrow_mut()
+                                .push_back(MyEvents2::UsedTool(new_map)); // Updates map of visualizer_2
+                            self.actual_state.as_ref().replace(States::NeedsExploring);
+                        }
                     }
-                },
+                }
+                Action::Rest => {}
             }
             update_map(world, Rc::clone(&self.charted_map));
-        } else {
-            self.actual_state.replace(States::Neutral);
         }
+
+        self.observation.replace(observe(self, world));
     }
 
     // Pushes events to the visualizer
     fn handle_event(&mut self, event: robotics_lib::event::events::Event) {
-        push_event(MyEvent::RobLib(event.clone())); // Push events to visualizer_1
+        self.recording
+            .borrow_mut()
+            .push_event(MyEvent::RobLib(event.clone())); // Push events to visualizer_1
 
         // Push event to visualizer_2
         match event {
@@ -485,7 +817,9 @@ impl Runnable for MyRobot {
             robotics_lib::event::events::Event::TimeChanged(_) => {}
             robotics_lib::event::events::Event::DayChanged(_) => {}
             robotics_lib::event::events::Event::EnergyRecharged(_) => {}
-            robotics_lib::event::events::Event::EnergyConsumed(_) => {}
+            robotics_lib::event::events::Event::EnergyConsumed(n) => {
+                *self.energy_consumed.borrow_mut() += n;
+            }
             robotics_lib::event::events::Event::Moved(_, _) => {
                 self.past_events
                     .as_ref()
@@ -528,6 +862,17 @@ impl Runnable for MyRobot {
     }
 }
 
+// The robot does nothing this tick and recharges. Consecutive rests are shown as a single one by visualizer_2
+fn rest(robot: &mut MyRobot) {
+    robot.recording.borrow_mut().push_event(MyEvent::Rested);
+
+    if !matches!(robot.past_events.borrow().back(), Some(MyEvents2::Rested)) {
+        robot.past_events.borrow_mut().push_back(MyEvents2::Rested);
+    }
+
+    robot.actual_state.replace(States::Rested);
+}
+
 // Function used at the end of each tick to update the information in the ChartingTools, in order to easily reach the best Banks and Markets
 pub(crate) fn update_map(world: &mut World, internal_map: Rc<RefCell<ChartedMap<Content>>>) {
     let map = robot_map(world).unwrap();
@@ -671,6 +1016,60 @@ pub(crate) fn update_map(world: &mut World, internal_map: Rc<RefCell<ChartedMap<
     }
 }
 
+// What the robot knows at the end of a tick, encoded in the StateKey used by the Q-table
+pub(crate) fn observe(robot: &MyRobot, world: &World) -> Observation {
+    let backpack = robot.get_backpack();
+    let backpack_used = backpack.get_contents().values().sum();
+    let coins = backpack
+        .get_contents()
+        .get(&Content::Coin(0))
+        .cloned()
+        .unwrap_or(0);
+    let charted_map = robot.charted_map.borrow();
+    let map = robot_map(world).unwrap_or_default();
+    let discovered_tiles = map.iter().flatten().filter(|tile| tile.is_some()).count();
+
+    // Distance to the nearest location of the content in the ChartedMap, which update_map keeps to the banks
+    // and markets that can still be used, so the map is not scanned again
+    let (row, col) = (
+        robot.get_coordinate().get_row(),
+        robot.get_coordinate().get_col(),
+    );
+    let nearest = |content: Content| {
+        charted_map.get(&content).and_then(|found| {
+            found
+                .iter()
+                .map(|val| val.0 .0.abs_diff(row) + val.0 .1.abs_diff(col))
+                .min()
+        })
+    };
+
+    // The contents of which the ChartedMap has at least a location
+    let known_contents = Collectable::ALL
+        .into_iter()
+        .filter(|item| {
+            charted_map
+                .get(&item.content())
+                .is_some_and(|found| found.iter().next().is_some())
+        })
+        .collect();
+
+    Observation {
+        energy: robot.get_energy().get_energy_level(),
+        backpack_used,
+        backpack_size: backpack.get_size(),
+        backpack_contents: backpack.get_contents().clone(),
+        coins,
+        market_known: charted_map.get_most(&Content::Market(0)).is_some(),
+        bank_known: charted_map.get_most(&Content::Bank(0..0)).is_some(),
+        known_contents,
+        discovered_tiles,
+        bank_distance: nearest(Content::Bank(0..0)),
+        market_distance: nearest(Content::Market(0)),
+        map_size: map.len(),
+    }
+}
+
 pub(crate) fn check_backpack(robot: &impl Runnable) -> States {
     let backpack = robot.get_backpack();
     let mut coins = 0;
@@ -710,3 +1109,104 @@ pub(crate) fn full(robot: &impl Runnable) -> bool {
 
     return false;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A robot with room for 10 items, carrying the given contents
+    fn carrying(contents: &[(Content, usize)]) -> Observation {
+        let backpack_contents: HashMap<Content, usize> = contents.iter().cloned().collect();
+        Observation {
+            backpack_used: backpack_contents.values().sum(),
+            backpack_size: 10,
+            coins: *backpack_contents.get(&Content::Coin(0)).unwrap_or(&0),
+            backpack_contents,
+            ..Observation::default()
+        }
+    }
+
+    #[test]
+    fn rest_needs_missing_energy() {
+        let mut observation = carrying(&[]);
+        assert!(!Action::Rest.is_valid(&observation));
+
+        observation.energy = MAX_ENERGY - 1;
+        assert!(Action::Rest.is_valid(&observation));
+    }
+
+    #[test]
+    fn only_rest_without_energy() {
+        let mut observation = carrying(&[(Content::Fish(0), 1), (Content::Coin(0), 1)]);
+        observation.market_known = true;
+        observation.bank_known = true;
+        observation.energy = 0;
+
+        let valid: Vec<Action> = Action::ALL
+            .into_iter()
+            .filter(|a| a.is_valid(&observation))
+            .collect();
+        assert_eq!(valid, vec![Action::Rest]);
+    }
+
+    #[test]
+    fn sell_needs_sellable_contents_and_a_market() {
+        let mut observation = carrying(&[(Content::Garbage(0), 3), (Content::Coin(0), 2)]);
+        observation.market_known = true;
+        assert!(!Action::Sell.is_valid(&observation));
+
+        for content in [Content::Tree(0), Content::Rock(0), Content::Fish(0)] {
+            let mut observation = carrying(&[(content, 1)]);
+            assert!(!Action::Sell.is_valid(&observation));
+            observation.market_known = true;
+            assert!(Action::Sell.is_valid(&observation));
+        }
+    }
+
+    #[test]
+    fn recycle_needs_five_garbage() {
+        assert!(!Action::Recycle.is_valid(&carrying(&[(Content::Garbage(0), 4)])));
+        assert!(Action::Recycle.is_valid(&carrying(&[(Content::Garbage(0), 5)])));
+    }
+
+    #[test]
+    fn deposit_needs_coins_and_a_bank() {
+        let mut observation = carrying(&[(Content::Coin(0), 1)]);
+        assert!(!Action::DepositInBank.is_valid(&observation));
+        observation.bank_known = true;
+        assert!(Action::DepositInBank.is_valid(&observation));
+        assert!(!Action::DepositInBank.is_valid(&carrying(&[])));
+    }
+
+    #[test]
+    fn destroy_needs_a_known_content_and_room() {
+        let mut observation = carrying(&[(Content::Rock(0), 9)]);
+        assert!(!Action::DestroyTree.is_valid(&observation));
+
+        observation.known_contents.insert(Collectable::Tree);
+        assert!(Action::DestroyTree.is_valid(&observation));
+        assert!(!Action::DestroyFish.is_valid(&observation));
+
+        observation.backpack_used = observation.backpack_size;
+        assert!(!Action::DestroyTree.is_valid(&observation));
+    }
+
+    #[test]
+    fn mask_allows_the_valid_actions() {
+        let mut observation = carrying(&[(Content::Fish(0), 1)]);
+        observation.market_known = true;
+
+        let mask = ActionMask::new(&observation);
+        for action in Action::ALL {
+            assert_eq!(mask.allows(action), action.is_valid(&observation));
+        }
+        assert_eq!(
+            mask.actions(),
+            vec![
+                Action::Sell,
+                Action::ExploreNearings,
+                Action::ExploreUnknown
+            ]
+        );
+    }
+}
@@ -2,8 +2,8 @@ use std::{
     collections::HashMap,
     fs::{self, File},
     io,
+    path::Path,
     process::Command,
-    time::{Duration, Instant},
 };
 
 use oxagaudiotool::{sound_config::OxAgSoundConfig, OxAgAudioTool};
@@ -52,42 +52,38 @@ fn main() {
                         let _ = audio_tool
                             .play_audio(&OxAgSoundConfig::new("src/lib/audio/waiting_music.mp3"));
 
-                        'a: for i in 0..1 {
-                            println!("training: {i}");
-                            let start = Instant::now();
-                            // let mut training_session = Command::new("cargo")
-                            //     .args(&["run", "--release", "--bin", "trainer"])
-                            //     .spawn()
-                            //     .expect("Error: could not run the trainer.");
-                            let mut training_session = Command::new("./target/release/trainer")
-                                // .args(&["run", "--release", "--bin", "trainer"])
-                                .spawn()
-                                .expect("Error: could not run the trainer.");
-
-                            // sometime the world does not have enough content and the simulation does not end
-                            // then we kill any simulation running more than 4 seconds
-                            while start.elapsed() < Duration::new(4, 0) {
-                                let mut terminated = false;
-                                // if trainer process has terminated no need to wait 4 seconds just continue to next for iteration
-                                training_session
-                                    .try_wait()
-                                    .map(|o| {
-                                        if let Some(_) = o {
-                                            terminated = true;
-                                        }
-                                    })
-                                    .expect("Error checking child process.");
-                                if terminated {
-                                    continue 'a;
-                                }
-                            }
-
-                            // kill the trainer
-                            training_session.kill().expect("Error killing the trainer.");
+                        println!("training...");
+                        // let mut training_session = Command::new("cargo")
+                        //     .args(&["run", "--release", "--bin", "trainer"])
+                        //     .spawn()
+                        //     .expect("Error: could not run the trainer.");
+                        // episodes that cannot reach the goal are stopped by the trainer itself,
+                        // so we just wait for it to write the Q-table and exit
+                        // the world selected in the settings of visualizer 1 is written next to the rewards
+                        let mut trainer = Command::new("./target/release/trainer");
+                        if Path::new("training_world.ron").exists() {
+                            trainer.args(["--world", "training_world.ron"]);
+                        }
+                        let status = trainer.status().expect("Error: could not run the trainer.");
+                        // the trainer exits with 3 or 4 when no episode reached the goal, depending on how most of them ended,
+                        // and with 5 when a stage of the curriculum failed
+                        match status.code() {
+                            Some(0) => {}
+                            Some(3) => println!(
+                                "The training completed, but the robot never reached the goal: it mostly ran out of ticks."
+                            ),
+                            Some(4) => println!(
+                                "The training completed, but the robot never reached the goal: it mostly stopped making progress."
+                            ),
+                            Some(5) => println!(
+                                "The training stopped because a stage of the curriculum missed its success rate."
+                            ),
+                            _ => println!("The training did not complete successfully."),
                         }
 
-                        // remove the rewards file
+                        // remove the rewards and world files
                         let _ = fs::remove_file("./rewards.ron");
+                        let _ = fs::remove_file("./training_world.ron");
                     }
                     Err(_) => {
                         println!("No user training detected. Terminating.")
@@ -0,0 +1,98 @@
+use std::{env, fs, path::Path, process::exit};
+
+use ai::{
+    config::{TrainingConfig, WorldConfig},
+    export::{
+        decisions_by_outcome, decisions_by_state, decisions_to_html, decisions_to_markdown,
+        record_episodes, transition_graph,
+    },
+    reward::TableRewards,
+    training::TrainerConfig,
+    utils::load_q_table_from,
+};
+
+// Episodes recorded for the transition graph when not given
+const EPISODES: usize = 5;
+
+// The episodes are played with the files the trainer reads by default, those that exist
+fn trainer_config() -> Result<TrainerConfig, String> {
+    let mut config = TrainerConfig::default();
+    if Path::new("rewards.ron").exists() {
+        config.rewards =
+            TableRewards::load("rewards.ron").map_err(|e| format!("Loading rewards.ron: {e}"))?;
+    }
+    if Path::new("training.ron").exists() {
+        config.training = TrainingConfig::load("training.ron")
+            .map_err(|e| format!("Loading training.ron: {e}"))?;
+    }
+    if Path::new("world.ron").exists() {
+        config.generator =
+            WorldConfig::load("world.ron").map_err(|e| format!("Loading world.ron: {e}"))?;
+    }
+    Ok(config)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("Usage: policy_export <q_table> [<episodes>]");
+        eprintln!("- writes the greedy action of every state to policy.md and policy.html");
+        eprintln!("- plays the episodes with the table and writes the transitions between states to policy.dot");
+        exit(2);
+    }
+
+    let q_table = match load_q_table_from(&args[0]) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Q-Table loading: {e}");
+            exit(1);
+        }
+    };
+    let episodes = match args.get(1).map(|arg| arg.parse::<usize>()) {
+        None => EPISODES,
+        Some(Ok(episodes)) => episodes,
+        Some(Err(_)) => {
+            eprintln!("The number of episodes must be a positive number.");
+            exit(2);
+        }
+    };
+    let config = match trainer_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            exit(2);
+        }
+    };
+
+    let by_outcome = decisions_by_outcome(&q_table);
+    let by_state = decisions_by_state(&q_table);
+
+    let markdown = format!(
+        "# Policy of {}\n\n## By outcome\n\n{}\n## By state\n\n{}",
+        args[0],
+        decisions_to_markdown(&by_outcome),
+        decisions_to_markdown(&by_state)
+    );
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<body>\n<h1>Policy of {}</h1>\n<h2>By outcome</h2>\n{}<h2>By state</h2>\n{}</body>\n</html>\n",
+        args[0],
+        decisions_to_html(&by_outcome),
+        decisions_to_html(&by_state)
+    );
+
+    println!("Recording {episodes} episodes...");
+    let results = record_episodes(q_table, episodes, &config);
+    let graph = transition_graph(&results);
+
+    for (path, content) in [
+        ("policy.md", markdown),
+        ("policy.html", html),
+        ("policy.dot", graph),
+    ] {
+        if let Err(e) = fs::write(path, content) {
+            eprintln!("File write: {e}");
+            exit(1);
+        }
+        println!("Written {path}");
+    }
+}
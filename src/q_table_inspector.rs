@@ -0,0 +1,97 @@
+use std::{env, process::exit};
+
+use ai::{
+    state::StateKey,
+    utils::{greedy_action, load_q_table_from, Action, QTable},
+};
+
+// Width of the columns of the printed matrix
+const STATE_WIDTH: usize = 70;
+const CELL_WIDTH: usize = 16;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.len() {
+        1 => {
+            let table = load(&args[0]);
+            println!("{}", args[0]);
+            print_table(&table);
+        }
+        2 => {
+            let old = load(&args[0]);
+            let new = load(&args[1]);
+            println!("{} -> {}", args[0], args[1]);
+            print_diff(&old, &new);
+        }
+        _ => {
+            eprintln!("Usage: q_table_inspector <q_table> [<other_q_table>]");
+            eprintln!("- with one table, prints it as a StateKey x Action matrix, * marks the greedy action");
+            eprintln!("- with two tables, prints the difference of each cell and the greedy actions that changed");
+            exit(2);
+        }
+    }
+}
+
+fn load(path: &str) -> QTable {
+    match load_q_table_from(path) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Q-Table loading: {e}");
+            exit(1);
+        }
+    }
+}
+
+fn print_header() {
+    let mut header = format!("{:<STATE_WIDTH$}", "");
+    for action in Action::ALL {
+        header += &format!("{:>CELL_WIDTH$}", format!("{action:?}"));
+    }
+    println!("{header}");
+}
+
+// Prints the table, the greedy action of each state is marked with a *
+fn print_table(table: &QTable) {
+    print_header();
+    for state in StateKey::all() {
+        let best = greedy_action(table, state);
+        let mut row = format!("{:<STATE_WIDTH$}", format!("{state}"));
+        for action in Action::ALL {
+            let marker = if action == best { "*" } else { " " };
+            row += &format!(
+                "{:>CELL_WIDTH$}",
+                format!("{:.3}{marker}", table[&(state, action)])
+            );
+        }
+        println!("{row}");
+    }
+}
+
+// Prints new - old for every cell, then the states whose greedy action is not the same anymore
+fn print_diff(old: &QTable, new: &QTable) {
+    print_header();
+    for state in StateKey::all() {
+        let mut row = format!("{:<STATE_WIDTH$}", format!("{state}"));
+        for action in Action::ALL {
+            let diff = new[&(state, action)] - old[&(state, action)];
+            row += &format!("{:>CELL_WIDTH$}", format!("{diff:+.3} "));
+        }
+        println!("{row}");
+    }
+
+    println!();
+    let mut changed = 0;
+    for state in StateKey::all() {
+        let old_best = greedy_action(old, state);
+        let new_best = greedy_action(new, state);
+        if old_best != new_best {
+            println!("{state}: {old_best:?} -> {new_best:?}");
+            changed += 1;
+        }
+    }
+    println!(
+        "{changed} of {} greedy actions changed.",
+        StateKey::all().len()
+    );
+}
@@ -0,0 +1,50 @@
+use std::{env, fs, path::Path, process::exit};
+
+use ai::sweep::{report, sweep, write_best, SweepConfig};
+
+// Directory of the report and of the best configuration when not given
+const OUTPUT: &str = "sweep";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("Usage: sweep <sweep.ron> [<output_directory>]");
+        eprintln!("- trains and evaluates every configuration of the sweep, then ranks them");
+        eprintln!("- writes sweep_report.md, and rewards.ron and custom_q_table.txt of the best configuration");
+        exit(2);
+    }
+    let output = args.get(1).map(String::as_str).unwrap_or(OUTPUT);
+
+    let config = match SweepConfig::load(&args[0]) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Sweep loading: {e}");
+            exit(2);
+        }
+    };
+
+    let results = match sweep(&config) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("Sweep: {e}");
+            exit(1);
+        }
+    };
+    let Some(best) = results.first() else {
+        eprintln!("The sweep has no configurations.");
+        exit(2);
+    };
+
+    let table = report(&results);
+    println!("{table}");
+
+    if let Err(e) = write_best(best, output) {
+        eprintln!("Best configuration writing: {e}");
+        exit(1);
+    }
+    if let Err(e) = fs::write(Path::new(output).join("sweep_report.md"), table) {
+        eprintln!("File write: {e}");
+        exit(1);
+    }
+    println!("Report and best configuration written to {output}");
+}
@@ -1,12 +1,299 @@
-use std::{fs::File, io::Read};
+use std::{env, path::Path, process::exit, str::FromStr};
+
+use ai::{
+    ai::EpisodeStatus,
+    checkpoint::Checkpoint,
+    config::{seed_or_random, Exploration, TrainingConfig, ValueFunction, WorldConfig},
+    curriculum::{load_curriculum, train_curriculum},
+    metrics::MetricsConfig,
+    reward::TableRewards,
+    training::{train, StopReason, TrainerConfig, TrainingReport},
+};
+
+// Number of episodes played by a training session when not given, the Q-table is saved after each of them
+const EPISODES: usize = 10;
+const WORLD_SIZE: usize = 100;
+const CHECKPOINT: &str = "training_checkpoint.ron";
+
+// Exit codes, so that scripts can tell why a session failed
+const TRAINING_ERROR: i32 = 1;
+const CONFIG_ERROR: i32 = 2;
+const TIMED_OUT: i32 = 3;
+const STALLED: i32 = 4;
+const STAGE_FAILED: i32 = 5;
+
+const USAGE: &str = "Usage: trainer [<seed>] [options]
+Options:
+  --seed <n>            seed of the session, random when missing
+  --rewards <file>      rewards of the robot (default: rewards.ron)
+  --config <file>       hyperparameters (default: training.ron if it exists)
+  --curriculum <file>   stages played instead of the episodes (default: curriculum.ron if it exists)
+  --world <file>        settings of the world generator (default: world.ron if it exists)
+  --input <file>        learner to start from (default: the file of the algorithm)
+  --output <file>       file the learner is written to (default: the input file)
+  --blank               start from a learner that was never trained, refused if the output file exists
+  --checkpoint <file>   state of the session, written after every episode (default: training_checkpoint.ron)
+  --resume              continue the session of the checkpoint, with its seed and number of episodes
+  --world-size <n>      size of the worlds (default: 100)
+  --episodes <n>        episodes to play (default: 10)
+  --max-ticks <n>       tick budget of each episode
+  --alpha <x>           learning rate, between 0 and 1
+  --gamma <x>           discount factor, between 0 and 1
+  --epsilon <x>         constant exploration rate, between 0 and 1
+  --help                print this message
+Exit codes: 0 success, 1 training error, 2 configuration error, 3 no episode reached the goal and most
+  of them ran out of ticks, 4 no episode reached the goal and most of them stalled, 5 a stage of the
+  curriculum missed its success rate";
+
+// What the command line sets, None keeps the default
+#[derive(Default)]
+struct Options {
+    seed: Option<u64>,
+    rewards: Option<String>,
+    config: Option<String>,
+    curriculum: Option<String>,
+    world: Option<String>,
+    input: Option<String>,
+    output: Option<String>,
+    blank: bool,
+    checkpoint: Option<String>,
+    resume: bool,
+    world_size: Option<usize>,
+    episodes: Option<usize>,
+    max_ticks: Option<usize>,
+    alpha: Option<f64>,
+    gamma: Option<f64>,
+    epsilon: Option<f64>,
+}
+
+fn parse<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {option}: {value}"))
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    let mut first = true;
+    while let Some(arg) = args.next() {
+        // The seed can still be given alone as the first argument, like the visualizers take it
+        if first && !arg.starts_with("--") {
+            options.seed = Some(parse("the seed", arg)?);
+            first = false;
+            continue;
+        }
+        first = false;
+
+        match arg.as_str() {
+            "--blank" => {
+                options.blank = true;
+                continue;
+            }
+            "--resume" => {
+                options.resume = true;
+                continue;
+            }
+            _ => {}
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {arg}"))?;
+        match arg.as_str() {
+            "--seed" => options.seed = Some(parse(arg, value)?),
+            "--rewards" => options.rewards = Some(value.clone()),
+            "--config" => options.config = Some(value.clone()),
+            "--curriculum" => options.curriculum = Some(value.clone()),
+            "--world" => options.world = Some(value.clone()),
+            "--input" => options.input = Some(value.clone()),
+            "--output" => options.output = Some(value.clone()),
+            "--checkpoint" => options.checkpoint = Some(value.clone()),
+            "--world-size" => options.world_size = Some(parse(arg, value)?),
+            "--episodes" => options.episodes = Some(parse(arg, value)?),
+            "--max-ticks" => options.max_ticks = Some(parse(arg, value)?),
+            "--alpha" => options.alpha = Some(parse(arg, value)?),
+            "--gamma" => options.gamma = Some(parse(arg, value)?),
+            "--epsilon" => options.epsilon = Some(parse(arg, value)?),
+            _ => return Err(format!("Unknown option: {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+// Builds the session from the options and the configuration files, returns it with the number of episodes
+fn build_config(options: Options) -> Result<(TrainerConfig, usize), String> {
+    let rewards_path = options.rewards.as_deref().unwrap_or("rewards.ron");
+    let rewards =
+        TableRewards::load(rewards_path).map_err(|e| format!("Loading {rewards_path}: {e}"))?;
+
+    // Hyperparameters are optional, if the default file is missing the default ones are used
+    let mut training = match &options.config {
+        Some(path) => TrainingConfig::load(path).map_err(|e| format!("Loading {path}: {e}"))?,
+        None if Path::new("training.ron").exists() => TrainingConfig::load("training.ron")
+            .map_err(|e| format!("Loading training.ron: {e}"))?,
+        None => TrainingConfig::default(),
+    };
+    if let Some(alpha) = options.alpha {
+        training.learning_rate = alpha;
+    }
+    if let Some(gamma) = options.gamma {
+        training.discount = gamma;
+    }
+    if let Some(epsilon) = options.epsilon {
+        training.exploration = Exploration::Constant(epsilon);
+    }
+    if let Some(max_ticks) = options.max_ticks {
+        training.limits.max_ticks = Some(max_ticks);
+    }
+    training.validate()?;
+
+    // With a curriculum the stages replace the fixed number of episodes
+    let curriculum = match &options.curriculum {
+        Some(path) => load_curriculum(path).map_err(|e| format!("Loading {path}: {e}"))?,
+        None if Path::new("curriculum.ron").exists() => {
+            load_curriculum("curriculum.ron").map_err(|e| format!("Loading curriculum.ron: {e}"))?
+        }
+        None => Vec::new(),
+    };
+
+    // So are the world settings, the default ones are the worlds the default robot was trained on
+    let generator = match &options.world {
+        Some(path) => WorldConfig::load(path).map_err(|e| format!("Loading {path}: {e}"))?,
+        None if Path::new("world.ron").exists() => {
+            WorldConfig::load("world.ron").map_err(|e| format!("Loading world.ron: {e}"))?
+        }
+        None => WorldConfig::default(),
+    };
+    let world_size = options.world_size.unwrap_or(WORLD_SIZE);
+    if world_size == 0 {
+        return Err("The world size must be at least 1".to_string());
+    }
+
+    // A resumed session keeps the seed and the number of episodes of its checkpoint when they are not given,
+    // the other settings must be given again and are checked against it
+    let checkpoint_path = options.checkpoint.unwrap_or_else(|| CHECKPOINT.to_string());
+    let mut seed = options.seed;
+    let mut episodes = options.episodes;
+    if options.resume {
+        if !curriculum.is_empty() {
+            return Err("A curriculum cannot be resumed".to_string());
+        }
+        if !Path::new(&checkpoint_path).exists() {
+            return Err(format!(
+                "{checkpoint_path} does not exist, there is nothing to resume"
+            ));
+        }
+        let checkpoint = Checkpoint::load(&checkpoint_path)
+            .map_err(|e| format!("Loading {checkpoint_path}: {e}"))?;
+        seed = seed.or(Some(checkpoint.seed));
+        episodes = episodes.or(Some(checkpoint.episodes));
+    }
+
+    let config = TrainerConfig {
+        world_size,
+        generator,
+        rewards,
+        default_rewards: false,
+        input: options.input,
+        output: options.output,
+        blank: options.blank,
+        checkpoint_every: Some(1),
+        checkpoint: Some(checkpoint_path),
+        resume: options.resume,
+        seed: seed_or_random(seed),
+        training,
+        metrics: Some(MetricsConfig {
+            episodes_path: "training_metrics.csv".to_string(),
+            ticks_path: None,
+        }),
+        curriculum,
+        ..TrainerConfig::default()
+    };
+
+    // A missing Q-table would only be noticed once the training starts, linear weights start from zero
+    let input = config.input_path();
+    if !config.blank
+        && !config.resume
+        && config.training.value_function == ValueFunction::Table
+        && !Path::new(&input).exists()
+    {
+        return Err(format!(
+            "{input} does not exist, use --blank to start from scratch"
+        ));
+    }
+    // Starting from scratch must not throw away a trained learner
+    let output = config.output_path();
+    if config.blank && !config.resume && Path::new(&output).exists() {
+        return Err(format!(
+            "{output} already exists, use --output to write the new learner to another file"
+        ));
+    }
+
+    Ok((config, episodes.unwrap_or(EPISODES)))
+}
 
-use ai::ai::ai;
 fn main() {
-    let mut file = File::open("rewards.ron").expect("File should exist.");
-    let mut buffer = String::new();
-    file.read_to_string(&mut buffer)
-        .expect("Error reading the RON file.");
-    let rewards: [f64; 8] = ron::from_str(&buffer).expect("Error deserializing.");
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{USAGE}");
+        return;
+    }
+
+    let (config, episodes) = match parse_args(&args).and_then(build_config) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("{USAGE}");
+            exit(CONFIG_ERROR);
+        }
+    };
+
+    let result = if config.curriculum.is_empty() {
+        train(episodes, &config)
+    } else {
+        train_curriculum(&config)
+    };
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Training: {e}");
+            exit(TRAINING_ERROR);
+        }
+    };
+    let coins: usize = report.summaries.iter().map(|s| s.coins_deposited).sum();
+    println!(
+        "Training done: {} episodes, {coins} coins deposited, learner written to {}.",
+        report.summaries.len(),
+        config.output_path()
+    );
+    println!("Training stopped because {}.", report.stop);
+
+    let code = exit_code(&report);
+    if code != 0 {
+        exit(code);
+    }
+}
 
-    ai(100, rewards.into(), false);
+// A failed curriculum stage fails the session. Otherwise a single episode reaching the goal is a success,
+// since the first episodes of a session are expected to fail; when none did, the most common failure is reported
+fn exit_code(report: &TrainingReport) -> i32 {
+    if let StopReason::StageFailed { .. } = report.stop {
+        return STAGE_FAILED;
+    }
+    let count = |status: EpisodeStatus| {
+        report
+            .summaries
+            .iter()
+            .filter(|s| s.status == status)
+            .count()
+    };
+    if report.summaries.is_empty() || count(EpisodeStatus::GoalReached) > 0 {
+        return 0;
+    }
+    eprintln!("No episode reached the goal.");
+    if count(EpisodeStatus::Stalled) > count(EpisodeStatus::TimedOut) {
+        STALLED
+    } else {
+        TIMED_OUT
+    }
 }
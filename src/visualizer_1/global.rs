@@ -23,5 +23,9 @@ pub(crate) const ROBOT_Z_INDEX: f32 = 2.0;
 /******************* AI DATA ********************/
 // AI data
 pub(crate) const WORLD_SIZE: usize = 200;
+// Settings of the world generator, added to the presets of the menu setting when the file exists
+pub(crate) const WORLD_FILE: &str = "world.ron";
+// Settings of the world selected in the menu setting, written for the training started from the menu
+pub(crate) const TRAINING_WORLD_FILE: &str = "training_world.ron";
 pub(crate) const MAX_ENERGY_LEVEL: usize = 1000;
 // Default values for the reward function
\ No newline at end of file
@@ -4,6 +4,7 @@
 /// The `update_my_backpack_and_make_sounds` function is the system that updates the backpack based on simulation events from the Robotic Lib.
 /// It also trigger a sound effect relative to the content.
 use super::*;
+use ai::goal::Collectable;
 use bevy::utils::HashMap;
 use robotics_lib::{
     event::events::Event as RoboticLibEvent, world::tile::Content as RoboticLibContent,
@@ -69,12 +70,17 @@ fn update_my_backpack_and_make_sounds(
     mut simulation_data: ResMut<SimulationData>,
     mut my_backpack: ResMut<MyBackPack>,
     mut total_transaction: ResMut<TotalTransactions>,
+    mut progress: ResMut<MissionProgress>,
     asset_server: Res<AssetServer>,
 ) {
     if let Some(event) = simulation_data.simulation_events.front() {
         // Check if the robot grabbed something or received some money after selling content in market.
         if let MyEvent::RobLib(RoboticLibEvent::AddedToBackpack(content, quantity)) = event {
             my_backpack.add_to_backpack(content.clone(), *quantity);
+            // Update the progress toward a Collect goal
+            if let Some(item) = Collectable::of_content(content) {
+                *progress.0.collected.entry(item).or_insert(0) += quantity;
+            }
             // Update transactions
             *total_transaction
                 .0
@@ -102,6 +108,10 @@ fn update_my_backpack_and_make_sounds(
         {
             my_backpack.remove_from_backpack(content.clone(), *quantity);
 
+            // Markets don't buy garbage, the robot only gets rid of it by recycling
+            if let RoboticLibContent::Garbage(_) = content {
+                progress.0.garbage_recycled += quantity;
+            }
             if let RoboticLibContent::Coin(_) = content {
                 // Play sound.
                 commands.spawn(AudioBundle {
@@ -24,6 +24,10 @@ impl Default for MyEnergy {
     }
 }
 
+/// Whether the robot is recharging instead of acting, shown next to the energy level.
+#[derive(Resource, Debug, Default)]
+pub struct RobotResting(pub bool);
+
 impl Display for MyEnergy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -34,18 +38,42 @@ pub struct EnergyPlugin;
 
 impl Plugin for EnergyPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<MyEnergy>().add_systems(
-            Update,
-            update_my_energy.run_if(in_state(SimulationState::Simulation)),
-        );
+        app.init_resource::<MyEnergy>()
+            .init_resource::<RobotResting>()
+            .add_systems(
+                Update,
+                update_my_energy.run_if(in_state(SimulationState::Simulation)),
+            );
     }
 }
 
 /// System used to update the energy throug the events.
-fn update_my_energy(mut simulation: ResMut<SimulationData>, mut my_energy: ResMut<MyEnergy>) {
+fn update_my_energy(
+    mut simulation: ResMut<SimulationData>,
+    mut my_energy: ResMut<MyEnergy>,
+    mut resting: ResMut<RobotResting>,
+) {
     if let Some(event) = simulation.simulation_events.front() {
+        // The robot rested, the ticks spent recharging are shown all at once.
+        if let MyEvent::Rested = event {
+            let mut tot_energy_recharged = 0;
+            simulation.simulation_events.pop_front();
+
+            loop {
+                match simulation.simulation_events.front() {
+                    Some(MyEvent::Rested) => {}
+                    Some(MyEvent::RobLib(RoboticLibEvent::EnergyRecharged(energy))) => {
+                        tot_energy_recharged += *energy;
+                    }
+                    _ => break,
+                }
+                simulation.simulation_events.pop_front();
+            }
+            my_energy.recharge(tot_energy_recharged);
+            resting.0 = true;
+        }
         // Energy recharged.
-        if let MyEvent::RobLib(RoboticLibEvent::EnergyRecharged(energy)) = event {
+        else if let MyEvent::RobLib(RoboticLibEvent::EnergyRecharged(energy)) = event {
             let mut tot_energy_recharged = *energy;
             simulation.simulation_events.pop_front();
 
@@ -71,6 +99,8 @@ fn update_my_energy(mut simulation: ResMut<SimulationData>, mut my_energy: ResMu
                 simulation.simulation_events.pop_front();
             }
             my_energy.consume(tot_energy_consumed);
+            // Consuming energy means the robot is acting again.
+            resting.0 = false;
         }
     }
 }
@@ -1,13 +1,17 @@
 use super::*;
 /// This module contains all the data regarding the robot interaction with the world that need to be visualized.
-use crate::global::{TILE_SIZE, WORLD_SIZE};
-use ai::data_storage::*;
+use crate::global::{TILE_SIZE, WORLD_FILE, WORLD_SIZE};
+use ai::{
+    config::WorldConfig,
+    data_storage::*,
+    goal::{Goal, GoalProgress},
+};
 use bevy::utils::HashMap;
 use robotics_lib::{
     event::events::Event as RoboticLibEvent,
     world::tile::{Content as RoboticLibContent, Tile as RoboticLibTile},
 };
-use std::{collections::VecDeque, fmt::Display};
+use std::{collections::VecDeque, fmt::Display, path::Path};
 
 pub(crate) mod backpack;
 pub(crate) mod energy;
@@ -17,6 +21,8 @@ pub struct SimulationDataPlugIn;
 impl Plugin for SimulationDataPlugIn {
     fn build(&self, app: &mut App) {
         app.init_resource::<WorldSize>()
+            .init_resource::<WorldSettings>()
+            .init_resource::<SimulationSeed>()
             .init_resource::<TotalTransactions>()
             .add_systems(OnExit(SimulationState::Loading), setup_simulation_data);
     }
@@ -31,6 +37,68 @@ impl Default for WorldSize {
         WorldSize(WORLD_SIZE)
     }
 }
+/// Settings of the world generator, one of the presets of the AI or the world.ron file of the current directory.
+/// This can be modified in the menu setting by the user.
+#[derive(Resource, Debug)]
+pub(crate) struct WorldSettings {
+    pub(crate) presets: Vec<(String, WorldConfig)>,
+    pub(crate) selected: usize,
+}
+
+/// When world.ron exists it is added to the presets and selected.
+impl Default for WorldSettings {
+    fn default() -> Self {
+        let mut presets: Vec<(String, WorldConfig)> = WorldConfig::presets()
+            .into_iter()
+            .map(|(name, config)| (name.to_string(), config))
+            .collect();
+        let mut selected = 0;
+        if Path::new(WORLD_FILE).exists() {
+            match WorldConfig::load(WORLD_FILE) {
+                Ok(config) => {
+                    selected = presets.len();
+                    presets.push((WORLD_FILE.to_string(), config));
+                }
+                Err(e) => eprintln!("Error loading {WORLD_FILE}: {e}"),
+            }
+        }
+        WorldSettings { presets, selected }
+    }
+}
+
+impl WorldSettings {
+    pub(crate) fn name(&self) -> &str {
+        &self.presets[self.selected].0
+    }
+    pub(crate) fn config(&self) -> &WorldConfig {
+        &self.presets[self.selected].1
+    }
+    /// Selects the next preset, or the previous one, going around the list.
+    pub(crate) fn cycle(&mut self, forward: bool) {
+        let total = self.presets.len();
+        self.selected = if forward {
+            (self.selected + 1) % total
+        } else {
+            (self.selected + total - 1) % total
+        };
+    }
+}
+
+/// Seed of the simulation, the same seed will generate the same world and the same robot behaviour.
+#[derive(Resource, Debug)]
+pub(crate) struct SimulationSeed(pub(crate) u64);
+
+/// It can be passed as the first argument of the visualizer, otherwise it is random.
+impl Default for SimulationSeed {
+    fn default() -> Self {
+        SimulationSeed(ai::config::seed_from_args())
+    }
+}
+
+/// What the AI or the showcase recorded, set up as SimulationData once the loading is over.
+#[derive(Resource, Debug)]
+pub(crate) struct SimulationRecording(pub(crate) Recording);
+
 /// Struct that holds the map the robot has visited, its spawn position and all the events of its interaction with the world.
 #[derive(Resource, Debug)]
 pub struct SimulationData {
@@ -63,49 +131,46 @@ impl SimulationData {
     }
 }
 
-/// Total coins the robot has to deposit in order to accomplish its mission.
-#[derive(Resource)]
-pub struct CoinsToDeposit {
-    pub total: usize,
-    pub deposited: usize,
-}
-impl CoinsToDeposit {
-    fn new(total: usize) -> Self {
-        CoinsToDeposit {
-            total,
-            deposited: 0,
-        }
-    }
-}
+/// The mission of the robot, shown in the Goal panel.
+#[derive(Resource, Debug)]
+pub(crate) struct ActiveGoal(pub(crate) Goal);
+
+/// What the robot has done toward the ActiveGoal so far while the simulation is replayed.
+#[derive(Resource, Default)]
+pub struct MissionProgress(pub GoalProgress);
 
 /// System that set up the simulation data at after the AI has finished the computation.
-fn setup_simulation_data(mut commands: Commands, world_size: Res<WorldSize>) {
+fn setup_simulation_data(
+    mut commands: Commands,
+    world_size: Res<WorldSize>,
+    recording: Res<SimulationRecording>,
+) {
     // Recover the events.
-    let mut simulation_events = SIMULATION_EVENTS.lock().unwrap().clone();
+    let mut simulation_events = recording.0.events.clone();
 
     // Push a terminated event.
     simulation_events.push_back(MyEvent::RobLib(RoboticLibEvent::Terminated));
 
     // Recover spawn position and convert it to Bevy coordinate system.
-    let setup = SETUP_DATA.lock().unwrap().clone();
+    let spawn_position = recording.0.robot_spawn_position;
     let robot_initial_position = (
-        setup.robot_spawn_position.1 as f32 * TILE_SIZE,
-        (world_size.0 - 1 - setup.robot_spawn_position.0) as f32 * TILE_SIZE,
+        spawn_position.1 as f32 * TILE_SIZE,
+        (world_size.0 - 1 - spawn_position.0) as f32 * TILE_SIZE,
     );
 
     let map = SimulationData::new(
-        &INITIAL_MAP.lock().unwrap(),
+        &recording.0.initial_map,
         world_size.0,
         robot_initial_position,
         simulation_events,
     );
-    let ctd = CoinsToDeposit::new((world_size.0.pow(2) as f32 * 0.002) as usize);
+    let progress = MissionProgress::default();
     let av_res = AvailableContent(HashMap::new());
 
     // Insert the info into Bevy.
     commands.insert_resource(map);
     commands.insert_resource(av_res);
-    commands.insert_resource(ctd);
+    commands.insert_resource(progress);
 }
 
 /// Total transaction to print on screen when the robot has finished.
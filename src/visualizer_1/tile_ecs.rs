@@ -1,7 +1,9 @@
 use super::{
     asset_loader::{ContentImages, TilesImages},
     robot::RobotTag,
-    simulation_data::{AvailableContent, CoinsToDeposit, SimulationData, WorldSize},
+    simulation_data::{
+        AvailableContent, MissionProgress, SimulationData, SimulationSeed, WorldSize,
+    },
     sprite_animation::{AnimationIndices, AnimationTimer},
     *,
 };
@@ -10,7 +12,7 @@ use crate::global::{CONTENT_Z_INDEX, TILE_SIZE, TILE_Z_INDEX};
 /// Every tile is an entity and any optional content is an entity as well spawned as a child to the tile entity.
 use ai::data_storage::MyEvent;
 use bevy::utils::hashbrown::HashSet;
-use rand::{rngs::ThreadRng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use robotics_lib::{
     event::events::Event as RoboticLibEvent,
     world::tile::{Content as RoboticLibContent, Tile, TileType},
@@ -61,6 +63,10 @@ pub struct Coordinate {
     pub y: usize,
 }
 
+/// Random generator used to pick the sprites, seeded with the simulation seed so the same run looks the same.
+#[derive(Resource)]
+pub struct SpriteRng(StdRng);
+
 /// Struct used to make visible only the tiles the robot has discovered until that moment.
 #[derive(Component)]
 pub struct NotDiscovered(bool);
@@ -88,13 +94,14 @@ fn spawn_tiles(
     content_images: Res<ContentImages>,
     simulation_data: Res<SimulationData>,
     mut available_content: ResMut<AvailableContent>,
+    seed: Res<SimulationSeed>,
 ) {
+    // Prepare the seeded range to pick a random sprite for an entity when they are multiple.
+    let mut rng = StdRng::seed_from_u64(seed.0);
+
     // Iter through the map builded in SimulationData Resource with Bevy coordinate system.
     for (i, row) in simulation_data.map.iter().enumerate() {
         for (j, tile_data) in row.iter().enumerate() {
-            // Prepare thread range to pick a random sprite for an entity when they are multiple.
-            let mut rng = rand::thread_rng();
-
             // Match all the different tiles:
             //  - texture_atlas: handle with the media loaded in the asset_loader module.
             //  - index: indicates either the first index of an animation or the actual image to pick in the atlas in case is not animated
@@ -232,7 +239,7 @@ fn spawn_tiles(
                 get_content_bundle(
                     tile_data.clone(),
                     &content_images,
-                    rng,
+                    &mut rng,
                     &mut available_content,
                 )
             {
@@ -252,6 +259,9 @@ fn spawn_tiles(
             }
         }
     }
+
+    // Keep the generator for the content spawned during the simulation.
+    commands.insert_resource(SpriteRng(rng));
 }
 /// Function that get info about the tile and content and prepare a bundle that will
 /// hold all the components necessary to represent a content entity in Bevy.
@@ -259,7 +269,7 @@ fn spawn_tiles(
 fn get_content_bundle(
     tile_data: Option<Tile>,
     content_images: &Res<ContentImages>,
-    mut rng: ThreadRng,
+    rng: &mut StdRng,
     available_content: &mut ResMut<AvailableContent>,
 ) -> Option<(
     Handle<TextureAtlas>,
@@ -630,6 +640,7 @@ fn discover_tiles(
     robot_query: Query<&Coordinate, With<RobotTag>>,
     mut simulation_data: ResMut<SimulationData>,
     world_size: Res<WorldSize>,
+    mut progress: ResMut<MissionProgress>,
 ) {
     // check if the robot explored tiles without moving and retreive the tiles
     let mut discovered_tiles = HashSet::new();
@@ -645,6 +656,7 @@ fn discover_tiles(
             *visibility = Visibility::Visible;
             // remove the tag not discovered
             commands.entity(id).remove::<NotDiscovered>();
+            progress.0.explored += 1.0 / (world_size.0 * world_size.0) as f64;
         }
     }
 }
@@ -665,7 +677,8 @@ fn update_tiles(
     content_images: Res<ContentImages>,
     tile_images: Res<TilesImages>,
     mut available_content: ResMut<AvailableContent>,
-    mut coins_to_deposit: ResMut<CoinsToDeposit>,
+    mut progress: ResMut<MissionProgress>,
+    mut sprite_rng: ResMut<SpriteRng>,
 ) {
     // check if the next event is related to a tile change.
     if let Some(MyEvent::RobLib(RoboticLibEvent::TileContentUpdated(updated_tile, (row, col)))) =
@@ -699,7 +712,7 @@ fn update_tiles(
                         get_content_bundle(
                             Some(updated_tile.clone()),
                             &content_images,
-                            rand::thread_rng(),
+                            &mut sprite_rng.0,
                             &mut available_content,
                         )
                     {
@@ -775,7 +788,7 @@ fn update_tiles(
                                     if let RoboticLibContent::Bank(old_qty) = content.0.clone() {
                                         let deposited_coin = new_quantity.start - old_qty.start;
 
-                                        coins_to_deposit.deposited += deposited_coin;
+                                        progress.0.coins_deposited += deposited_coin;
                                         *available_content
                                             .0
                                             .entry(RoboticLibContent::Bank(0..0))
@@ -1,4 +1,4 @@
-use ai::ai::REWARDS;
+use ai::{ai::REWARDS, reward::TableRewards};
 /// Module managing the custom reward resource for the custom training bot.
 use bevy::prelude::*;
 
@@ -11,6 +11,13 @@ impl Default for TrainingValues {
     }
 }
 
+impl TrainingValues {
+    /// Reward function the custom bot is trained with.
+    pub(crate) fn reward_function(&self) -> TableRewards {
+        TableRewards(self.0)
+    }
+}
+
 pub(crate) struct TrainingValuesPlugin;
 
 impl Plugin for TrainingValuesPlugin {
@@ -1,8 +1,8 @@
 /// This is the user interface to show the info during the visualization of the simulation.
 /// It will hold:
-/// - the energy level
+/// - the energy level, and whether the robot is resting
 /// - the backpack content
-/// - the amount of coin to reach the goal and how many has been deposited
+/// - the goal and the progress toward it
 /// - commands button do control the velocity of the simulation, zooming and music
 ///
 /// I had to use two different window for the map and the UI as for now the UI nodes in bevy create some issue if mixed with world entities.
@@ -10,13 +10,17 @@ use self::simulation_data::{SimulationData, TotalTransactions, Transaction};
 use super::menu::{despawn_screen, MenuButtonAction};
 use super::style::*;
 use crate::{
-    simulation_data::{backpack::MyBackPack, energy::MyEnergy, AvailableContent, CoinsToDeposit},
+    simulation_data::{
+        backpack::MyBackPack,
+        energy::{MyEnergy, RobotResting},
+        ActiveGoal, AvailableContent, MissionProgress,
+    },
     spawn_box_node, spawn_button, spawn_container_node, spawn_heading_node,
     spawn_sub_container_node,
     ui::menu::Change,
     *,
 };
-use ai::data_storage::MyEvent;
+use ai::{data_storage::MyEvent, goal::GoalProgress};
 use bevy::window::PrimaryWindow;
 use robotics_lib::{
     event::events::Event as RoboticLibEvent, world::tile::Content as RoboticLibContent,
@@ -83,7 +87,7 @@ fn setup_info_simulation(
     asset_server: Res<AssetServer>,
     energy: Res<MyEnergy>,
     backpack: Res<MyBackPack>,
-    coin_to_deposit: Res<CoinsToDeposit>,
+    goal: Res<ActiveGoal>,
     window: Query<&Window, With<PrimaryWindow>>,
 ) {
     // Number of nodes to compute the right height for each one relative to the monitor.
@@ -135,7 +139,7 @@ fn setup_info_simulation(
         content_to_push.push(content_box);
     }
     // Goal
-    let heading_goal_val = format!("Goal [{}]:", coin_to_deposit.total);
+    let heading_goal_val = format!("Goal [{}]:", goal.0);
     let coin_to_deposit_heading = spawn_heading_node![
         commands,
         heading_goal_val,
@@ -147,7 +151,7 @@ fn setup_info_simulation(
         commands,
         asset_server,
         "ui_icons/piggy.png",
-        "0",
+        goal.0.progress(&GoalProgress::default()),
         CoinsToDepositNode,
         box_height
     ];
@@ -260,13 +264,19 @@ fn show_simulation_data(
         (Without<EnergyValueNode>, Without<CoinsToDepositNode>),
     >,
     energy: Res<MyEnergy>,
+    resting: Res<RobotResting>,
     backpack: Res<MyBackPack>,
     _available_content: Res<AvailableContent>,
-    coins_to_deposit: Res<CoinsToDeposit>,
+    goal: Res<ActiveGoal>,
+    progress: Res<MissionProgress>,
 ) {
     // Energy value
     let mut testo = testo_e.single_mut();
-    testo.sections[0].value = energy.0.to_string();
+    testo.sections[0].value = if resting.0 {
+        format!("{} (resting)", energy.0)
+    } else {
+        energy.0.to_string()
+    };
 
     // Backpack values
     for (mut text, node) in backpack_q.iter_mut() {
@@ -279,7 +289,7 @@ fn show_simulation_data(
 
     // Goal value
     let mut testo = testo_c.single_mut();
-    testo.sections[0].value = coins_to_deposit.deposited.to_string();
+    testo.sections[0].value = goal.0.progress(&progress.0);
 }
 
 /// Helper function to translate from a node to a RoboticLibContent
@@ -4,10 +4,11 @@ use crate::{
         CustomRobotImages, DefaultRobotImages, RobotPath, CUSTOM_ROBOT_PATH, DEFAULT_ROBOT_PATH,
     },
     button_text_style,
+    global::TRAINING_WORLD_FILE,
     music::AmbientMusic,
     robot::MoveRobotTimer,
-    showcase::{self, WORLD_SIZE_BIOME, WORLD_SIZE_10X10},
-    simulation_data::WorldSize,
+    showcase::{self, WORLD_SIZE_10X10, WORLD_SIZE_BIOME},
+    simulation_data::{ActiveGoal, SimulationRecording, SimulationSeed, WorldSettings, WorldSize},
     spawn_animation_button, spawn_button, spawn_button_showcase, spawn_container_node,
     spawn_heading_node, spawn_setting_value_node, spawn_sub_container_node,
     sprite_animation::AnimationTimer,
@@ -15,11 +16,16 @@ use crate::{
     windows_cameras::{MapCamera, MapWindow},
     SimulationState,
 };
-use ai::ai::REWARDS;
+use ai::{
+    config::{Mode, TrainingConfig},
+    data_storage::take_recording,
+    goal::Goal,
+    reward::TableRewards,
+};
 /// This file holds all the different screens of the user iterface before the simulation begin.
 /// So the Menu has different state corresponding to different screen:
 /// - Main Menu: from here the user can either start the simulation or go to the other screen/state of the menu
-/// - Setting Menu: set the size of the world, the robot to be used
+/// - Setting Menu: set the size of the world, the settings of the world generator, the robot to be used
 /// - Training Menu: change the default value of the Q-learning algorithm of the AI in order to train a custom AI
 /// - Showcase Menu: from here you can check out the Planner Tool and World Generator
 ///
@@ -154,6 +160,7 @@ pub(crate) enum MenuButtonAction {
 
     Settings,
     Size(Change),
+    World(Change),
     DefaultBot,
     CustomBot,
 
@@ -205,8 +212,11 @@ pub struct Showcase {
 
 /// This system will run the simulation after the user press Start.
 fn run_simulation(
+    mut commands: Commands,
     time: Res<Time>,
     world_size: Res<WorldSize>,
+    world_settings: Res<WorldSettings>,
+    seed: Res<SimulationSeed>,
     mut loading_timer: ResMut<LoadingTimer>,
     mut simulation_state: ResMut<NextState<SimulationState>>,
     showcase: Res<Showcase>,
@@ -215,30 +225,50 @@ fn run_simulation(
     loading_timer.tick(time.delta());
     if loading_timer.just_finished() {
         // run the rustici tool showcase
-        if showcase.set {
-            match showcase.biome_type {
-                // If biome is selected will run the test with our world generator 30x30 
-                Some(_) => showcase::run(WORLD_SIZE_BIOME, showcase.biome_type),
+        let (recording, goal) = if showcase.set {
+            let world_size = match showcase.biome_type {
+                // If biome is selected will run the test with our world generator 30x30
+                Some(_) => WORLD_SIZE_BIOME,
                 // If no biome is selected it will run on a simple hardcoded world 10x10
-                None => showcase::run(WORLD_SIZE_10X10, showcase.biome_type),
-            }
+                None => WORLD_SIZE_10X10,
+            };
+            showcase::run(world_size, showcase.biome_type);
+            (take_recording(), Goal::for_world(world_size))
         // run AI simulation
         } else {
-            ai::ai::ai(world_size.0, REWARDS.to_vec(), robot_path.is_default());
-        }
+            let result = ai::ai::ai(
+                world_size.0,
+                world_settings.config(),
+                &TableRewards::default(),
+                robot_path.is_default(),
+                &TrainingConfig::default(),
+                seed.0,
+                Mode::Evaluation,
+            );
+            (result.recording, result.goal)
+        };
+        commands.insert_resource(SimulationRecording(recording));
+        commands.insert_resource(ActiveGoal(goal));
         simulation_state.set(SimulationState::Simulation);
     }
 }
 
-/// System that save the user reward values into a file and exit from the Bevy App
-/// The file will be read from the entry_point.rs and there will start the training
+/// System that save the user reward values and the selected world settings into files and exit from the Bevy App
+/// The files will be read from the entry_point.rs and there will start the training
 /// of the customized bot.
 fn train_user_robot(
     training_values: ResMut<TrainingValues>,
+    world_settings: Res<WorldSettings>,
     mut app_exit_events: ResMut<Events<bevy::app::AppExit>>,
 ) {
-    let serialized = ron::to_string(&training_values.0).expect("Serialization failed.");
+    let serialized =
+        ron::to_string(&training_values.reward_function()).expect("Serialization failed.");
     let mut file = File::create("rewards.ron").expect("Failed to create file.");
+    file.write_all(serialized.as_bytes())
+        .expect("Failed to write to file.");
+    // A file of its own, so that the world.ron of the user is not overwritten
+    let serialized = ron::to_string(world_settings.config()).expect("Serialization failed.");
+    let mut file = File::create(TRAINING_WORLD_FILE).expect("Failed to create file.");
     file.write_all(serialized.as_bytes())
         .expect("Failed to write to file.");
     app_exit_events.send(AppExit);
@@ -352,6 +382,7 @@ fn settings_menu_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     world_size: Res<WorldSize>,
+    world_settings: Res<WorldSettings>,
     window: Query<&Window, (With<PrimaryWindow>, Without<MapWindow>)>,
     default_robot_handle: Res<DefaultRobotImages>,
     custom_robot_handle: Res<CustomRobotImages>,
@@ -379,6 +410,21 @@ fn settings_menu_setup(
         MenuButtonAction::Size(Change::Down),
         RealTimeText("world_size".to_string())
     ];
+    let world_setting = spawn_setting_value_node![
+        commands,
+        width * 0.8,
+        130.,
+        "menu_icons/world.png",
+        "World:",
+        button_text_style,
+        asset_server,
+        world_settings.name(),
+        ">",
+        "<",
+        MenuButtonAction::World(Change::Up),
+        MenuButtonAction::World(Change::Down),
+        RealTimeText("world".to_string())
+    ];
 
     let bot_button = commands
         .spawn(NodeBundle {
@@ -425,6 +471,7 @@ fn settings_menu_setup(
     commands.entity(sub_container).push_children(&[
         setting_heading,
         world_size_setting,
+        world_setting,
         bot_button,
         back_button,
     ]);
@@ -521,6 +568,7 @@ fn menu_action(
     mut simulation_state: ResMut<NextState<SimulationState>>,
     mut real_text_area: Query<(&mut Text, &RealTimeText)>,
     mut world_size: ResMut<WorldSize>,
+    mut world_settings: ResMut<WorldSettings>,
     mut query_camera: Query<&mut OrthographicProjection, With<MapCamera>>,
     mut timer: ResMut<MoveRobotTimer>,
     mut training_values: ResMut<TrainingValues>,
@@ -560,6 +608,15 @@ fn menu_action(
                         }
                     }
                 }
+                // Setting: world generator
+                MenuButtonAction::World(c) => {
+                    world_settings.cycle(*c == Change::Up);
+                    for (mut text, name) in real_text_area.iter_mut() {
+                        if name.0 == "world" {
+                            text.sections[0].value = world_settings.name().to_string();
+                        }
+                    }
+                }
                 // Setting: choose pretrained bot
                 MenuButtonAction::DefaultBot => {
                     if robot_path.0 == CUSTOM_ROBOT_PATH.to_string() {
@@ -13,7 +13,12 @@ use robotics_lib::{
 
 use std::{collections::VecDeque, process::exit};
 
-use ai::{ai::{ai, REWARDS}, my_events::MyEvents2};
+use ai::{
+    ai::ai,
+    config::{seed_from_args, Mode, TrainingConfig, WorldConfig},
+    my_events::MyEvents2,
+    reward::TableRewards,
+};
 use {
     backpack::Backpack, camera::Camera, sprite_manager::SpriteManager, time_manager::TimeManager,
 };
@@ -32,11 +37,18 @@ fn conf() -> Conf {
 #[macroquad::main(conf)]
 async fn main() {
     let size_world = 200;
-    let (pointer_to_events, pointer_to_map) = ai(size_world, REWARDS.to_vec(), true);
-    let mut events = pointer_to_events.borrow_mut();
-    let maps = pointer_to_map.as_ref().borrow_mut().clone();
+    let result = ai(
+        size_world,
+        &WorldConfig::default(),
+        &TableRewards::default(),
+        true,
+        &TrainingConfig::default(),
+        seed_from_args(),
+        Mode::Evaluation,
+    );
+    let mut events = result.robot_events;
 
-    let mut final_map = combine_maps(maps, size_world);
+    let mut final_map = combine_maps(result.maps, result.world_size);
     let mut robot_pos = get_starting_pos(&mut events);
 
     let mut time_manager = TimeManager::default();
@@ -54,6 +66,7 @@ async fn main() {
     update_tiles(&mut displayed_world, robot_pos.0, robot_pos.1, &final_map);
     displayed_world[robot_pos.0][robot_pos.1] = final_map[robot_pos.0][robot_pos.1].clone();
     let mut show_menu = true;
+    let mut resting = false;
     while !events.is_empty() {
         if time_manager.should_update() {
             update_tick(
@@ -62,6 +75,7 @@ async fn main() {
                 &mut events,
                 &mut final_map,
                 &mut robot_pos,
+                &mut resting,
             );
         }
 
@@ -85,6 +99,7 @@ async fn main() {
             &mut my_camera,
             current_speed,
             events_left,
+            resting,
         )
         .await;
         time_manager.update();
@@ -109,6 +124,7 @@ async fn main() {
             &mut my_camera,
             current_speed,
             events_left,
+            false,
         )
         .await;
         time_manager.update();
@@ -159,6 +175,7 @@ async fn visualize(
     my_camera: &mut Camera,
     current_speed: usize,
     events_left: f32,
+    resting: bool,
 ) {
     clear_background(GRAY);
 
@@ -175,6 +192,7 @@ async fn visualize(
         current_speed,
         events_left,
         show_menu,
+        resting,
     )
     .await;
 }
@@ -223,6 +241,7 @@ fn update_tick(
     events: &mut VecDeque<MyEvents2>,
     final_map: &mut Vec<Vec<Option<Tile>>>,
     robot_pos: &mut (usize, usize),
+    resting: &mut bool,
 ) {
     let next_event = events.pop_front();
     if next_event.is_none() {
@@ -230,6 +249,8 @@ fn update_tick(
     }
 
     let next_event = next_event.unwrap();
+    // The robot is resting until it does something else
+    *resting = matches!(next_event, MyEvents2::Rested);
     match next_event {
         MyEvents2::Event(Event::Moved(_, (x, y))) => {
             update_tiles(displayed_world, x, y, &final_map);
@@ -265,6 +286,7 @@ fn update_tick(
             _ => {}
         },
         MyEvents2::RobotSpawned(_) => {}
+        MyEvents2::Rested => {}
 
         MyEvents2::Event(Event::AddedToBackpack(content, quantity)) => match content {
             Content::Rock(_) => backpack.add(backpack::BackpackContent::Rock, quantity),
@@ -328,6 +350,7 @@ async fn write_debug(
     current_speed: usize,
     events_left: f32,
     show_menu: bool,
+    resting: bool,
 ) {
     set_default_camera();
     //TOP-LEFT corner
@@ -357,6 +380,11 @@ async fn write_debug(
     };
     draw_text(&format!("Current Mode: {}", mode), 10., y, offset, BLACK);
 
+    if resting {
+        y += offset;
+        draw_text("Robot: Resting", 10., y, offset, BLACK);
+    }
+
     //BOTTOM-LEFT corner
     y = screen_height() - offset + 5.;
     draw_text(